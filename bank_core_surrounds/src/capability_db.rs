@@ -215,6 +215,13 @@ pub fn read_db(bytes: &[u8]) -> Result<CapabilitiesDB, Error> {
 }
 
 
+#[cfg(test)]
+impl SurroundList {
+    pub fn new(names: &[&str]) -> Self {
+        SurroundList{names: names.iter().map(|x| x.to_string()).collect()}
+    }
+}
+
 impl Default for SurroundList {
     /// Default to an empty list.
     fn default() -> Self {
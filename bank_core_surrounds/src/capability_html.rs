@@ -6,6 +6,7 @@ use std::sync::MutexGuard;
 use crate::document::TwoTreeViewDocument;
 use crate::capability_tree::CapabilityData;
 use crate::surrounds::SurroundItem;
+use crate::theme::current_theme;
 use html_escape::encode_text;
 
 
@@ -105,11 +106,15 @@ pub fn surround_as_html(item: &SurroundItem) -> String {
 }
 
 
-/// Return the style tag needed to properly display capability data.
+/// Return the style tag needed to properly display capability data (in the colors of the
+/// current theme).
 #[allow(dead_code)] // this IS used, but from javascript
-pub fn style() -> &'static str {
-    r##"
-        .modal-shade {
+pub fn style() -> String {
+    let theme = current_theme();
+    let overlay = &theme.overlay;
+    format!(
+        r##"
+        .modal-shade {{
             position: fixed;
             z-index: 1;
             left: 0;
@@ -117,48 +122,55 @@ pub fn style() -> &'static str {
             width: 100%;
             height: 100%;
             overflow: auto;
-            background-color: #00000080;
+            background-color: {shade};
             display: flex;
             justify-content: center;
             align-items: center;
-        }
-        .modal-content {
-            background-color: #F7F7F7;
+        }}
+        .modal-content {{
+            background-color: {background};
+            color: {text};
             padding: 6px;
-            border: 3px solid #000000;
+            border: 3px solid {border};
             overflow: scroll;
             max-height: 90%;
             max-width: 90%;
-        }
-        .item_data {
+        }}
+        .item_data {{
             max-width: 420px;
-            font-family: Arial, sans-serif;
+            font-family: {font_family}, sans-serif;
             font-size: 16px;
-            border: 2px solid #000000;
+            border: 2px solid {border};
             padding: 5px;
-        }
-        .item_data label {
+        }}
+        .item_data label {{
             font-weight: bold;
-        }
-        .item_data > div {
+        }}
+        .item_data > div {{
             margin: 10px 2px;
-        }
-        .item_data .name {
+        }}
+        .item_data .name {{
             font-size: 20px;
             font-weight: bold;
-        }
-        .used_by_grid {
+        }}
+        .used_by_grid {{
             display: inline-grid;
             grid-template-columns: max-content max-content;
             grid-gap: 0;
-            border-right: 1px solid #000000;
-            border-bottom: 1px solid #000000;
-        }
-        .used_by_grid > div {
-            border-top: 1px solid #000000;
-            border-left: 1px solid #000000;
+            border-right: 1px solid {border};
+            border-bottom: 1px solid {border};
+        }}
+        .used_by_grid > div {{
+            border-top: 1px solid {border};
+            border-left: 1px solid {border};
             padding: 2px;
             margin: 0;
-        }
-"##
+        }}
+"##,
+        shade = overlay.shade,
+        background = overlay.background,
+        text = overlay.text,
+        border = overlay.border,
+        font_family = theme.font_family,
+    )
 }
\ No newline at end of file
@@ -1,13 +1,14 @@
 
 use std::collections::HashMap;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use itertools::Itertools;
 use prog_draw::data_tree::{
-    DTNode, DTNodeBuild, InvalidGrowth,TreeLayoutDirection, LAYOUT_DIRECTION,
+    DTNode, DTNodeBuild, InvalidGrowth,TraversalOrder, TreeLayoutDirection, LAYOUT_DIRECTION,
     DTNodeBuild::{AddData, EndChildren, StartChildren},
 };
-use prog_draw::svg_render::SvgPositioned;
-use prog_draw::geometry::{Coord, Rect};
+use prog_draw::svg_render::{Hyperlink, SvgPositioned};
+use prog_draw::geometry::{BBox, Coord, Point, Rect, Transform};
+use prog_draw::layers::{Layer, Layered, LayeredScene};
 use prog_draw::svg_writer::{Attributes, Renderable, TagWriter, TagWriterError};
 use prog_draw::text_size::get_system_text_sizer;
 use prog_draw::tidy_tree::{NULL_ID, TidyTree};
@@ -16,6 +17,7 @@ use crate::document::{
     BASELINE_RISE, COLLAPSE_DOT_RADIUS, NODE_ITEM_ROUND_CORNER,
     TEXT_ITEM_PADDING, LAYER_SPACING, ITEM_SPACING
 };
+use crate::theme::current_theme;
 use crate::capability_db::CapabilitiesDB;
 
 
@@ -51,11 +53,38 @@ pub struct CapabilityData {
     node_loc_style: NodeLocationStyle,
 }
 
+/// A node that is visible in a laid-out CapabilityNodeTree, along with the bounding box
+/// it was given (in the tree's own coordinates).
+#[derive(Debug)]
+pub struct PlacedCapability<'a> {
+    pub data: &'a CapabilityData,
+    pub parent: Option<&'a CapabilityData>,
+    pub bbox: Rect,
+    pub collapsed: bool,
+}
+
 #[derive(Debug)]
 pub struct CapabilityNodeTree {
     pub tree: DTNode<CapabilityData>,
     layout_direction: TreeLayoutDirection,
     tree_collapse_policy: TreeCollapsePolicy,
+    /// Where each visible node (by id) was put by the most recent layout.
+    drawn_locations: HashMap<String, Point>,
+    /// Where each visible node was before the most recent layout, so the change can be
+    /// animated. It's empty if there was nothing drawn before.
+    previous_locations: HashMap<String, Point>,
+}
+
+/// How a node is animated after the tree is laid out again. Each holds the offset from
+/// where the node is now to where the animation starts (or, for Shrink, ends).
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Transition {
+    /// It was drawn before, somewhere else, and glides to its new spot.
+    Glide(Point),
+    /// It wasn't drawn before, so it grows out of where its parent was.
+    Grow(Point),
+    /// It isn't drawn any more, so it shrinks into its (visible) ancestor.
+    Shrink(Point),
 }
 
 
@@ -70,8 +99,19 @@ thread_local!{
     /// This threadlocal variable is set before rendering to say which what rules should be
     /// used for sketching the tree.
     static TREE_COLLAPSE_POLICY: Cell<TreeCollapsePolicy> = Cell::new(Default::default());
+
+    /// This threadlocal variable is set before rendering to say whether nodes should be
+    /// animated from where they were before the last layout.
+    pub static ANIMATE_TRANSITIONS: Cell<bool> = const { Cell::new(false) };
+
+    /// This threadlocal variable holds the transition for each node id while a tree is
+    /// being rendered.
+    static NODE_TRANSITIONS: RefCell<HashMap<String, Transition>> = RefCell::new(HashMap::new());
 }
 
+/// How long the transitions after a layout take.
+const TRANSITION_TIME: &str = "0.4s";
+
 
 
 impl From<&str> for CoreOrSurround {
@@ -148,9 +188,19 @@ impl CapabilityData {
         Self::new(id_str, parent_id, text, used_by_set, description, core_surround, notes, collapsed)
     }
 
+    /// Returns the CSS class used when drawing this node: "root", "branch", or "leaf". A
+    /// collapsed branch is drawn as a leaf.
+    pub fn style_class(&self) -> &'static str {
+        match self.node_loc_style {
+            NodeLocationStyle::RootNode => "root",
+            NodeLocationStyle::BranchNode => if self.collapsed {"leaf"} else {"branch"},
+            NodeLocationStyle::LeafNode => "leaf",
+        }
+    }
+
     /// Returns the (width, height) of the text string.
     fn text_size(&self) -> (Coord, Coord) {
-        match get_system_text_sizer().text_size(&self.text, "Arial", 14.0) {
+        match get_system_text_sizer().text_size(&self.text, current_theme().font_family, 14.0) {
             Err(_) => panic!("Sizing isn't working."),
             Ok((width,height)) => (width as Coord, height as Coord)
         }
@@ -160,6 +210,37 @@ impl CapabilityData {
 
 impl Renderable for CapabilityData {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        // --- both trees can hold the same capability, so the id says which side it's on ---
+        let side = match LAYOUT_DIRECTION.with(|it| it.get()) {
+            Some(TreeLayoutDirection::Left) => "left",
+            _ => "right",
+        };
+        let mut attributes = Attributes::from([("id", format!("capability-{}-{}", side, self.id))]);
+        if let Some(transition) = NODE_TRANSITIONS.with(|it| it.borrow().get(&self.id).copied()) {
+            attributes = transition.add_to(attributes);
+        }
+        tag_writer.begin_tag("g", attributes)?;
+        let drawing = Hyperlink::new(CapabilityDrawing(self));
+        match self.description.as_str() {
+            "" => drawing.render(tag_writer)?,
+            description => drawing.with_tooltip(description).render(tag_writer)?,
+        }
+        tag_writer.end_tag("g")
+    }
+}
+
+/// Draws a capability; rendering the CapabilityData wraps this to add its tooltip.
+struct CapabilityDrawing<'a>(&'a CapabilityData);
+
+impl<'a> Renderable for CapabilityDrawing<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        self.0.render_drawing(tag_writer)
+    }
+}
+
+impl CapabilityData {
+    /// Draws the box, text, and collapse control for this capability.
+    fn render_drawing(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
         // --- Decide the dimensions of everything ---
         let (loc_x, loc_y) = self.location;
         let (text_width, text_height) = self.text_size();
@@ -176,18 +257,15 @@ impl Renderable for CapabilityData {
         let box_height = text_height + 2.0 * TEXT_ITEM_PADDING;
 
         // --- decide on decoration & color ---
-        let class: &str = match self.node_loc_style {
-            NodeLocationStyle::RootNode => "root",
-            NodeLocationStyle::BranchNode => if self.collapsed {"leaf"} else {"branch"},
-            NodeLocationStyle::LeafNode => "leaf",
-        };
+        let class = self.style_class();
         let (box_color, text_color) = get_color_strs(&self.used_by_set);
+        let theme = current_theme();
 
         // --- Decide how we're handling collapsed things ---
         struct JSReplaceData {
             control_cx: Coord,
             control_cy: Coord,
-            fill: String,
+            solid: bool,
             onclick: String,
         }
         let right_left = match LAYOUT_DIRECTION.with(|it| it.get()) {
@@ -198,52 +276,44 @@ impl Renderable for CapabilityData {
             (NodeLocationStyle::BranchNode, TreeCollapsePolicy::JavaScriptReplace) => Some(JSReplaceData{
                 control_cx: loc_x + box_width * right_left,
                 control_cy: loc_y,
-                fill: (if self.collapsed {"#000000"} else {"#FFFFFF"}).to_string(),
+                solid: self.collapsed,
                 onclick: format!("toggle_then_draw('{}')", self.id).to_string(),
             }),
             (_, _) => None,
         };
 
-        assert_eq!(box_width, self.get_bbox().width()); // FIXME: Remove... but this is useful.
+        assert_eq!(box_width, self.box_rect().width()); // FIXME: Remove... but this is useful.
 
         // --- draw it ---
         if self.node_loc_style != NodeLocationStyle::RootNode {
-            tag_writer.single_tag("rect", Attributes::from([
+            tag_writer.single_tag("rect", theme.node_box_style(box_color).add_to(Attributes::from([
                 ("x", &*box_left.to_string()),
                 ("y", &*box_top.to_string()),
                 ("width", &*box_width.to_string()),
                 ("height", &*box_height.to_string()),
                 ("rx", &*NODE_ITEM_ROUND_CORNER.to_string()),
-                ("fill", box_color),
-                ("stroke", "black"),
-                ("stroke-width", &*1.to_string()),
                 ("onclick", &format!("show_overlay_data('capability','{}')", self.id)),
                 ("class", class)
-            ]))?;
+            ])))?;
             tag_writer.tag_with_text(
                 "text",
-                Attributes::from([
+                theme.node_text_style(text_color).add_text_to(Attributes::from([
                     ("x", &*text_left.to_string()),
                     ("y", &*text_baseline.to_string()),
-                    ("font-family", "Arial"),
-                    ("fill", text_color),
-                    ("style", "font-style: normal; font-size: 12.4px; pointer-events: none"), // FIXME: size for 14 and set this to 12.4 seems to work. WHY?
+                    ("style", "font-style: normal; pointer-events: none"),
                     ("class", class),
-                ]),
+                ])),
                 &self.text
             )?;
             match jsreplace_data {
                 None => {},
                 Some(jsreplace_data) => {
-                    tag_writer.single_tag("circle", Attributes::from([
+                    tag_writer.single_tag("circle", theme.dot_style(jsreplace_data.solid).add_to(Attributes::from([
                         ("cx", &*jsreplace_data.control_cx.to_string()),
                         ("cy", &*jsreplace_data.control_cy.to_string()),
                         ("r", &*COLLAPSE_DOT_RADIUS.to_string()),
-                        ("fill", &jsreplace_data.fill),
-                        ("stroke", "#000000"),
-                        ("stroke-width", "1.0"),
                         ("onclick", &jsreplace_data.onclick),
-                    ]))?;
+                    ])))?;
                 },
             }
         }
@@ -252,12 +322,12 @@ impl Renderable for CapabilityData {
 }
 
 
-impl SvgPositioned for CapabilityData {
-    /// Gives the bounding box for the node including text AND the box around it. Remember, if
+impl CapabilityData {
+    /// Gives the rect for the node including text AND the box around it. Remember, if
     /// the node isn't correctly positioned yet, its location will be (0,0). Also know that
     /// self.location is the center-left or center-right of the box it occupies (depending
     /// on LAYOUT_DIRECTION).
-    fn get_bbox(&self) -> Rect {
+    pub fn box_rect(&self) -> Rect {
         let center = self.location;
         let (text_width, text_height) = self.text_size();
         let width = text_width + 2.0 * TEXT_ITEM_PADDING;
@@ -272,6 +342,12 @@ impl SvgPositioned for CapabilityData {
     }
 }
 
+impl SvgPositioned for CapabilityData {
+    fn get_bbox(&self) -> BBox {
+        self.box_rect().into()
+    }
+}
+
 
 /// Used internally, this just creates a mapping between strings and unique numbers.
 /// which will last as long as the NumberMapper does.
@@ -319,7 +395,14 @@ impl CapabilityNodeTree {
         let collapsed = false;
         let data = CapabilityData::new_new(id, parent_id, text, used_by_set, description, core_surround, notes, collapsed);
         let tree = DTNode::new(data);
-        CapabilityNodeTree {tree, layout_direction, tree_collapse_policy}
+        let drawn_locations = HashMap::new();
+        let previous_locations = HashMap::new();
+        CapabilityNodeTree {tree, layout_direction, tree_collapse_policy, drawn_locations, previous_locations}
+    }
+
+    /// Returns the direction this tree is laid out in.
+    pub fn layout_direction(&self) -> TreeLayoutDirection {
+        self.layout_direction
     }
 
     /// Adds nodes to the tree.
@@ -329,17 +412,9 @@ impl CapabilityNodeTree {
 
 
     pub fn find_data_by_id<'a>(&'a self, id: &str) -> Option<&'a CapabilityData> {
-        let mut node_stack: Vec<&DTNode<CapabilityData>> = vec![&self.tree];
-        while let Some(node) = node_stack.pop() {
-            if node.data.id == id {
-                return Some(&node.data)
-            } else {
-                for child in node.children.iter() {
-                    node_stack.push(child)
-                }
-            }
-        }
-        return None
+        self.tree.iter()
+            .map(|visit| &visit.node.data)
+            .find(|data| data.id == id)
     }
 
     /// Given the ID of a node, this returns the tree node containing that item or None if it doesn't
@@ -347,17 +422,7 @@ impl CapabilityNodeTree {
     ///
     /// FIXME: If I use this much I will need to make it efficient by maintaining a lookup table.
     pub fn find_node_by_id_mut<'a>(&'a mut self, id: &str) -> Option<&'a mut DTNode<CapabilityData>> {
-        let mut node_stack: Vec<&'a mut DTNode<CapabilityData>> = vec![&mut self.tree];
-        while let Some(node) = node_stack.pop() {
-            if node.data.id == id {
-                return Some(node)
-            } else {
-                for child in node.children.iter_mut() {
-                    node_stack.push(child)
-                }
-            }
-        }
-        return None
+        self.tree.find_mut(|data| data.id == id)
     }
 
     /// Adds a node to the tree. Uses the node's parent_id to determine where to add it.
@@ -392,75 +457,248 @@ impl CapabilityNodeTree {
             .collect(); // and collect into a hashmap
 
         // set the location field in each one.
-        populate_locations(&mut nums, &mut self.tree, &locations);
+        for visit in self.tree.traverse_mut(TraversalOrder::PreOrder, true) {
+            match locations.get(&nums.get_num(&visit.data.id)) {
+                None => panic!("All locations should be set but aren't."),
+                Some((x,y)) => visit.data.location = (*y, *x),
+            }
+        }
 
         // set the node_loc_style
         set_node_loc_style(&mut self.tree);
+
+        // --- remember where things were and are, so the change can be animated ---
+        let drawn_locations = self.placed_nodes().iter()
+            .map(|placed| (placed.data.id.clone(), placed.data.location))
+            .collect();
+        self.previous_locations = std::mem::replace(&mut self.drawn_locations, drawn_locations);
+    }
+
+    /// Call this when the tree is drawn again without a new layout, so that nothing in it
+    /// is animated. (It's as though it had moved from where it is to where it is.)
+    pub fn remember_locations(&mut self) {
+        self.previous_locations = self.drawn_locations.clone();
+    }
+
+    /// Returns how each node moves from where it was drawn before the last layout: first
+    /// the nodes that are visible now (by id), then the ones that have just been hidden.
+    fn transitions(&self) -> (HashMap<String, Transition>, Vec<(&CapabilityData, Transition)>) {
+        let mut transitions = HashMap::new();
+        let mut hidden = Vec::new();
+        if self.previous_locations.is_empty() {
+            return (transitions, hidden);
+        }
+        let offset = |from: Point, to: Point| (from.0 - to.0, from.1 - to.1);
+
+        // --- visible nodes start where they were, or (if new) where their parent was ---
+        let placed_nodes = self.placed_nodes();
+        let mut starts: HashMap<&str, Point> = HashMap::new();
+        for placed in placed_nodes.iter() {
+            let data = placed.data;
+            let (start, transition) = match self.previous_locations.get(&data.id) {
+                Some(previous) => (*previous, Transition::Glide(offset(*previous, data.location))),
+                None => {
+                    let start = placed.parent.and_then(|parent| starts.get(parent.id.as_str()).copied())
+                        .unwrap_or(data.location);
+                    (start, Transition::Grow(offset(start, data.location)))
+                },
+            };
+            starts.insert(&data.id, start);
+            if transition != Transition::Glide((0.0, 0.0)) {
+                transitions.insert(data.id.clone(), transition);
+            }
+        }
+
+        // --- nodes that were visible but aren't shrink into their nearest visible ancestor ---
+        // (hidden nodes aren't laid out, so they are still where they were last drawn)
+        let all_data: HashMap<&str, &CapabilityData> = self.tree.iter()
+            .map(|visit| (visit.node.data.id.as_str(), &visit.node.data))
+            .collect();
+        for (id, previous) in self.previous_locations.iter().sorted_by_key(|(id, _)| *id) {
+            if starts.contains_key(id.as_str()) {
+                continue;
+            }
+            if let Some(data) = all_data.get(id.as_str()).copied() {
+                let mut ancestor = all_data.get(data.parent_id.as_str()).copied();
+                while let Some(ancestor_data) = ancestor.filter(|x| !starts.contains_key(x.id.as_str())) {
+                    ancestor = all_data.get(ancestor_data.parent_id.as_str()).copied();
+                }
+                let end = ancestor.unwrap_or(&self.tree.data).location;
+                hidden.push((data, Transition::Shrink(offset(end, *previous))));
+            }
+        }
+        (transitions, hidden)
+    }
+
+    /// Returns every node that is visible (the root and anything not hidden inside a collapsed
+    /// node) in pre-order, along with its bounding box. Must be called after layout().
+    pub fn placed_nodes(&self) -> Vec<PlacedCapability<'_>> {
+        let existing_direction = LAYOUT_DIRECTION.with(|it| it.get());
+        LAYOUT_DIRECTION.with(|it| it.set(Some(self.layout_direction)));
+        let answer = self.tree.traverse(TraversalOrder::PreOrder, true)
+            .map(|visit| PlacedCapability{
+                data: &visit.node.data,
+                parent: visit.parent.map(|parent| &parent.data),
+                bbox: visit.node.data.box_rect(),
+                collapsed: visit.node.collapsed,
+            })
+            .collect();
+        LAYOUT_DIRECTION.with(|it| it.set(existing_direction));
+        answer
     }
 
     /// Toggles the collapsed state of a node. Leaf and Root nodes are unaffected. Calling this
     /// with a node_id not found in the tree has no affect. Returns true if the tree needs to
     /// be laid out again after this, and false if it doesn't.
-    ///
-    /// FIXME: if DTNode someday grows a method for iterating over the tree I could use
-    ///   that. Until then, it's done directly in this method.
     #[allow(dead_code)] // this IS used, but from javascript
     pub fn toggle_collapse(&mut self, node_id: &str) -> bool {
-        /// Output of Internal recursive subroutine.
-        enum Outcome {NotFound, FoundAndChanged, FoundNoChange}
-        /// Internal recursive subroutine.
-        fn toggle_collapse_node(node_id: &str, tree_node: &mut DTNode<CapabilityData>) -> Outcome {
-            if &tree_node.data.id == node_id {
-                match tree_node.data.node_loc_style {
-                    NodeLocationStyle::BranchNode => {
-                        tree_node.collapsed = ! tree_node.collapsed;
-                        tree_node.data.collapsed = !tree_node.data.collapsed;
-                        Outcome::FoundAndChanged
-                    },
-                    _ => Outcome::FoundNoChange,
-                }
-            } else {
-                for child in tree_node.children.iter_mut() {
-                    match toggle_collapse_node(node_id, child) {
-                        Outcome::NotFound => {},
-                        found_outcome => return found_outcome,
-                    }
-                }
-                Outcome::NotFound
-            }
-        }
-
-        // --- Call the recursive subroutine on the tree ---
-        match toggle_collapse_node(node_id, &mut self.tree) {
-            Outcome::NotFound => false,
-            Outcome::FoundNoChange => false,
-            Outcome::FoundAndChanged => true,
+        match self.tree.iter_mut().find(|visit| visit.data.id == node_id) {
+            Some(visit) if visit.data.node_loc_style == NodeLocationStyle::BranchNode => {
+                *visit.collapsed = !*visit.collapsed;
+                visit.data.collapsed = !visit.data.collapsed;
+                true
+            },
+            _ => false,
         }
     }
 }
 
 impl Renderable for CapabilityNodeTree {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        TreePart{tree: self, layer: Layer::Edges}.render(tag_writer)?;
+        TreePart{tree: self, layer: Layer::Nodes}.render(tag_writer)
+    }
+}
+
+impl Layered for CapabilityNodeTree {
+    fn add_to_layers<'a>(&'a self, scene: &mut LayeredScene<'a>, transform: Transform) {
+        scene.add_transformed(Layer::Edges, TreePart{tree: self, layer: Layer::Edges}, transform);
+        scene.add_transformed(Layer::Nodes, TreePart{tree: self, layer: Layer::Nodes}, transform);
+    }
+}
+
+/// The part of a CapabilityNodeTree that goes on one layer: the lines between the nodes
+/// (for Layer::Edges) or the nodes themselves (for any other layer).
+struct TreePart<'a> {
+    tree: &'a CapabilityNodeTree,
+    layer: Layer,
+}
+
+impl<'a> Renderable for TreePart<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        let (transitions, hidden) = match ANIMATE_TRANSITIONS.with(|it| it.get()) {
+            true => self.tree.transitions(),
+            false => Default::default(),
+        };
+        let animated = !transitions.is_empty() || !hidden.is_empty();
+        let existing_direction = LAYOUT_DIRECTION.with(|it| it.get());
+        LAYOUT_DIRECTION.with(|it| it.set(Some(self.tree.layout_direction)));
+        TREE_COLLAPSE_POLICY.with(|it| it.set(self.tree.tree_collapse_policy));
+        NODE_TRANSITIONS.with(|it| it.replace(transitions));
+        let rendered = match self.layer {
+            Layer::Edges => self.render_edges(tag_writer, animated),
+            _ => self.render_nodes(tag_writer, animated, &hidden),
+        };
+        NODE_TRANSITIONS.with(|it| it.borrow_mut().clear());
+        TREE_COLLAPSE_POLICY.with(|it| it.set(Default::default()));
+        LAYOUT_DIRECTION.with(|it| it.set(existing_direction));
+        rendered
+    }
+}
+
+impl<'a> TreePart<'a> {
+    /// Draws the lines, which fade in if anything moved (rather than trying to follow it).
+    fn render_edges(&self, tag_writer: &mut dyn TagWriter, animated: bool) -> Result<(), TagWriterError> {
+        let attributes = match animated {
+            true => Attributes::from([("class", "fade-in")]),
+            false => Attributes::new(),
+        };
+        tag_writer.begin_tag("g", attributes)?;
+        self.tree.tree.render_edges(tag_writer)?;
+        tag_writer.end_tag("g")
+    }
+
+    /// Draws the nodes, along with the ones that were just hidden as they shrink away.
+    fn render_nodes(
+        &self,
+        tag_writer: &mut dyn TagWriter,
+        animated: bool,
+        hidden: &[(&CapabilityData, Transition)]
+    ) -> Result<(), TagWriterError> {
         tag_writer.begin_tag("g", Attributes::new())?;
-        let style_text = r#"
+        let mut style_text = r#"
           text.leaf {
             pointer-events: none;
           }
-        "#;
-        tag_writer.tag_with_text("style", Attributes::new(), style_text)?;
-        let existing_direction = LAYOUT_DIRECTION.with(|it| it.get());
-        LAYOUT_DIRECTION.with(|it| it.set(Some(self.layout_direction)));
-        TREE_COLLAPSE_POLICY.with(|it| it.set(self.tree_collapse_policy));
-        self.tree.render(tag_writer)?;
-        TREE_COLLAPSE_POLICY.with(|it| it.set(Default::default()));
-        LAYOUT_DIRECTION.with(|it| it.set(existing_direction));
-        tag_writer.end_tag("g")?;
-        Ok(())
+        "#.to_string();
+        if animated {
+            style_text.push_str(&transition_css());
+        }
+        tag_writer.tag_with_text("style", Attributes::new(), &style_text)?;
+        self.tree.tree.render_nodes(tag_writer)?;
+        for (data, transition) in hidden.iter() {
+            tag_writer.begin_tag("g", transition.add_to(Attributes::new()))?;
+            CapabilityDrawing(data).render(tag_writer)?;
+            tag_writer.end_tag("g")?;
+        }
+        tag_writer.end_tag("g")
+    }
+}
+
+
+impl Transition {
+    /// Returns the attributes with the class and offsets for this transition added.
+    fn add_to(&self, attributes: Attributes) -> Attributes {
+        let (class, (dx, dy)) = match self {
+            Transition::Glide(offset) => ("glide", offset),
+            Transition::Grow(offset) => ("grow", offset),
+            Transition::Shrink(offset) => ("shrink", offset),
+        };
+        attributes
+            .with_field("class", class)
+            .with_field("style", format!("--dx: {}px; --dy: {}px", dx, dy))
+    }
+}
+
+/// Returns the CSS that runs the transitions. Each one moves by the offset in the --dx
+/// and --dy variables of the element, and scales around the element's own center.
+fn transition_css() -> String {
+    format!(r#"
+          .glide, .grow, .shrink {{
+            transform-box: fill-box;
+            transform-origin: center;
+          }}
+          .glide {{ animation: glide {time} ease-out; }}
+          .grow {{ animation: grow {time} ease-out; }}
+          .shrink {{ animation: shrink {time} ease-in forwards; pointer-events: none; }}
+          .fade-in {{ animation: fade-in {time} ease-in; }}
+          @keyframes glide {{ from {{ transform: translate(var(--dx), var(--dy)); }} }}
+          @keyframes grow {{ from {{ transform: translate(var(--dx), var(--dy)) scale(0); }} }}
+          @keyframes shrink {{ to {{ transform: translate(var(--dx), var(--dy)) scale(0); }} }}
+          @keyframes fade-in {{ from {{ opacity: 0; }} }}
+          @media (prefers-reduced-motion: reduce) {{
+            .glide, .grow, .fade-in {{ animation: none; }}
+            .shrink {{ display: none; }}
+          }}
+        "#, time = TRANSITION_TIME)
+}
+
+impl<'a> SvgPositioned for TreePart<'a> {
+    fn get_bbox(&self) -> BBox {
+        self.tree.get_bbox()
+    }
+}
+
+impl CapabilityNodeTree {
+    /// Returns the rect covering every visible node. (It's never empty: there is always
+    /// at least the root.)
+    pub fn box_rect(&self) -> Rect {
+        self.get_bbox().rect().unwrap()
     }
 }
 
 impl SvgPositioned for CapabilityNodeTree {
-    fn get_bbox(&self) -> Rect {
+    fn get_bbox(&self) -> BBox {
         let existing_direction = LAYOUT_DIRECTION.with(|it| it.get());
         LAYOUT_DIRECTION.with(|it| it.set(Some(self.layout_direction)));
         let answer = self.tree.get_bbox();
@@ -531,7 +769,7 @@ fn dummy_data() -> CapabilityNodeTree {
 
 /// Recursive function used in build_tidy_tree().
 fn add_to_tidy(nums: &mut NumberMapper, tidy: &mut TidyTree, dtnode: &DTNode<CapabilityData>, parent_id: &str) {
-    let data_bbox = dtnode.data.get_bbox();
+    let data_bbox = dtnode.data.box_rect();
     // note: width and height are swapped because we want to lay it out sideways not vertically
     tidy.add_node(nums.get_num(&dtnode.data.id), data_bbox.height(), data_bbox.width(), nums.get_num(parent_id));
     if !dtnode.collapsed {
@@ -541,20 +779,6 @@ fn add_to_tidy(nums: &mut NumberMapper, tidy: &mut TidyTree, dtnode: &DTNode<Cap
     }
 }
 
-/// Recursive function used in build_tidy_tree().
-fn populate_locations(nums: &mut NumberMapper, dtnode: &mut DTNode<CapabilityData>, locations: &HashMap<usize, (f64, f64)>) {
-    match locations.get(&nums.get_num(&dtnode.data.id)) {
-        None => panic!("All locations should be set but aren't."),
-        Some((x,y)) => dtnode.data.location = (*y, *x),
-    }
-    if !dtnode.collapsed {
-        for child in dtnode.children.iter_mut() {
-            populate_locations(nums, child, locations);
-        }
-    }
-}
-
-
 /// Sets the node_loc_style field in the entire tree.
 fn set_node_loc_style(dtnode: &mut DTNode<CapabilityData>) {
     dtnode.data.node_loc_style = NodeLocationStyle::RootNode;
@@ -620,3 +844,70 @@ pub fn read_trees_from_capdb(capdb: &CapabilitiesDB) -> [CapabilityNodeTree; 2]
     [core_tree, surround_tree]
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::use_fixed_width_text;
+
+    fn offset(from: Point, to: Point) -> Point {
+        (from.0 - to.0, from.1 - to.1)
+    }
+
+    fn location(tree: &CapabilityNodeTree, id: &str) -> Point {
+        tree.find_data_by_id(id).unwrap().location
+    }
+
+    #[test]
+    fn test_transitions() {
+        use_fixed_width_text();
+        let mut tree = CapabilityNodeTree::new(TreeLayoutDirection::Right, TreeCollapsePolicy::Nothing);
+        for (id, parent_id) in [("A", "ROOT"), ("A1", "A"), ("A2", "A"), ("B", "ROOT")] {
+            tree.add_node(CapabilityData::new_new(id, parent_id, id, UsedBySet::all_mixed(), "", CoreOrSurround::Blank, "", false));
+        }
+        tree.layout();
+        tree.layout();
+        let (transitions, hidden) = tree.transitions();
+        assert!(transitions.is_empty() && hidden.is_empty(), "nothing moved, so nothing is animated");
+
+        // --- a new node grows from where its parent was, and anything it pushed aside glides ---
+        let before: HashMap<String, Point> = tree.placed_nodes().iter()
+            .map(|placed| (placed.data.id.clone(), placed.data.location))
+            .collect();
+        tree.add_node(CapabilityData::new_new("B1", "B", "B1", UsedBySet::all_mixed(), "", CoreOrSurround::Blank, "", false));
+        tree.layout();
+        let (transitions, hidden) = tree.transitions();
+        assert!(hidden.is_empty());
+        assert_eq!(transitions["B1"], Transition::Grow(offset(before["B"], location(&tree, "B1"))));
+        let mut moved = 0;
+        for (id, previous) in before.iter() {
+            let now = location(&tree, id);
+            if now == *previous {
+                assert!(!transitions.contains_key(id));
+            } else {
+                assert_eq!(transitions[id], Transition::Glide(offset(*previous, now)));
+                moved += 1;
+            }
+        }
+        assert!(moved > 0);
+
+        // --- collapsing a node shrinks its children into it ---
+        let a1_before = location(&tree, "A1");
+        let a2_before = location(&tree, "A2");
+        assert!(tree.toggle_collapse("A"));
+        tree.layout();
+        let (_, hidden) = tree.transitions();
+        let hidden: Vec<(&str, Transition)> = hidden.iter().map(|(data, t)| (data.id.as_str(), *t)).collect();
+        let a_now = location(&tree, "A");
+        assert_eq!(hidden, vec![
+            ("A1", Transition::Shrink(offset(a_now, a1_before))),
+            ("A2", Transition::Shrink(offset(a_now, a2_before))),
+        ]);
+
+        // --- drawing again without a layout doesn't animate anything ---
+        tree.remember_locations();
+        let (transitions, hidden) = tree.transitions();
+        assert!(transitions.is_empty() && hidden.is_empty());
+    }
+}
@@ -1,26 +1,27 @@
-use prog_draw::svg_writer::{Attributes, Renderable, TagWriter, TagWriterError};
-use prog_draw::svg_render::SvgPositioned;
-use prog_draw::geometry::Rect;
+use prog_draw::svg_writer::{Renderable, TagWriter, TagWriterError};
+use prog_draw::style::Style;
+use prog_draw::svg_render::{Circle, SvgPositioned};
+use prog_draw::geometry::BBox;
 use crate::document::CENTER_DOT_RADIUS;
+use crate::theme::current_theme;
 
 
 pub struct CenterDot;
 
+impl CenterDot {
+    fn circle(&self) -> Circle {
+        Circle::new((0.0, 0.0), CENTER_DOT_RADIUS, Style::filled(current_theme().outline))
+    }
+}
+
 impl Renderable for CenterDot {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
-        tag_writer.single_tag("circle", Attributes::from([
-            ("cx", "0"),
-            ("cy", "0"),
-            ("r", &*CENTER_DOT_RADIUS.to_string()),
-        ]))?;
-        Ok(())
+        self.circle().render(tag_writer)
     }
 }
 
 impl SvgPositioned for CenterDot {
-    fn get_bbox(&self) -> Rect {
-        Rect::new_cwh((0.0,0.0), 2.0 * CENTER_DOT_RADIUS, 2.0 * CENTER_DOT_RADIUS)
+    fn get_bbox(&self) -> BBox {
+        self.circle().get_bbox()
     }
 }
-
-
@@ -3,11 +3,14 @@
 // implement it. This module creates one.
 //
 
-use prog_draw::geometry::{Coord, Point, Rect};
-use prog_draw::svg_render::SvgPositioned;
-use prog_draw::svg_writer::{Renderable, TagWriter, TagWriterError, Attributes};
+use prog_draw::geometry::{BBox, Coord, Path, Point};
+use prog_draw::style::Style;
+use prog_draw::marker::{Marker, MarkerShape};
+use prog_draw::svg_render::{PathShape, SvgPositioned};
+use prog_draw::svg_writer::{Renderable, TagWriter, TagWriterError};
 use crate::used_by::{get_color_strs, UsedBySet};
-use crate::document::CONNECT_DOT_RADIUS;
+use crate::document::{CONNECT_ARROW_SIZE, CONNECT_DOT_RADIUS};
+use crate::theme::current_theme;
 
 
 const LINE_CTRL_OFFSET: Coord = 10.0;
@@ -15,7 +18,10 @@ const LINE_CTRL_OFFSET: Coord = 10.0;
 
 
 #[derive(Debug)]
-struct Line {
+pub struct Line {
+    capability_id: String,
+    surround_id: String,
+    used_by_set: UsedBySet,
     start: Point,
     end: Point,
     color: &'static str
@@ -42,55 +48,99 @@ impl ConnectingLines {
 
     /// Call this to add in a new line. It's promised that we're in a mode where we can safely
     /// get bbox data for the DTNode.
-    pub fn add_line(&mut self, capability_pos: Point, surround_pos: Point, used_by_set: &UsedBySet) {
+    pub fn add_line(
+        &mut self,
+        capability_id: &str,
+        surround_id: &str,
+        capability_pos: Point,
+        surround_pos: Point,
+        used_by_set: &UsedBySet
+    ) {
+        let capability_id = capability_id.to_string();
+        let surround_id = surround_id.to_string();
+        let used_by_set = *used_by_set;
         let start = capability_pos;
         let end = surround_pos;
         let color = get_color_strs(&used_by_set).0;
-        let line: Line = Line{start, end, color};
+        let line: Line = Line{capability_id, surround_id, used_by_set, start, end, color};
         self.lines.push(line);
     }
+
+    /// Returns an iterator over the lines currently in this collection.
+    pub fn iter(&self) -> impl Iterator<Item=&Line> {
+        self.lines.iter()
+    }
+}
+
+
+impl Line {
+    /// Returns the id of the capability this line starts from.
+    pub fn capability_id(&self) -> &str {
+        self.capability_id.as_str()
+    }
+
+    /// Returns the id of the surround this line ends at.
+    pub fn surround_id(&self) -> &str {
+        self.surround_id.as_str()
+    }
+
+    /// Returns the point (on the capability) where this line starts.
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    /// Returns the point (on the surround) where this line ends.
+    pub fn end(&self) -> Point {
+        self.end
+    }
+
+    /// Returns the two control points of the curve this line is drawn with.
+    pub fn control_points(&self) -> (Point, Point) {
+        control_points(self.start, self.end)
+    }
+
+    /// Returns the curve this line is drawn along.
+    pub fn path(&self) -> Path {
+        let (start_ctrl, end_ctrl) = self.control_points();
+        Path::new(self.start).cubic_to(start_ctrl, end_ctrl, self.end)
+    }
+
+    /// Returns the color this line is drawn in.
+    pub fn color(&self) -> &'static str {
+        self.color
+    }
+
+    /// Returns the UsedBySet that decides the color of this line.
+    pub fn used_by_set(&self) -> &UsedBySet {
+        &self.used_by_set
+    }
 }
 
 
 
-fn make_line_path(left: Point, right: Point) -> String {
+/// Returns the two control points for a line from left to right.
+fn control_points(left: Point, right: Point) -> (Point, Point) {
     let left_ctrl_x = left.0 + LINE_CTRL_OFFSET;
     let right_ctrl_x = right.0 - LINE_CTRL_OFFSET;
-    format_args!(
-        "M {} {} C {} {}, {} {}, {} {}",
-        left.0, left.1,
-        left_ctrl_x, left.1,
-        right_ctrl_x, right.1,
-        right.0, right.1
-    ).to_string()
+    ((left_ctrl_x, left.1), (right_ctrl_x, right.1))
 }
 
 
+impl Line {
+    /// Returns the shape the line is drawn with: the curve, with a dot where it leaves the
+    /// capability and an arrow pointing at the surround that implements it.
+    fn shape(&self) -> PathShape {
+        let theme = current_theme();
+        let start_marker = Marker::new(MarkerShape::Dot, 2.0 * CONNECT_DOT_RADIUS, theme.dot_style(false));
+        let end_marker = Marker::new(MarkerShape::Arrow, CONNECT_ARROW_SIZE, theme.node_box_style(self.color));
+        PathShape::new(self.path(), Style::new().with_stroke(self.color, 2.0))
+            .with_markers(Some(start_marker), Some(end_marker))
+    }
+}
+
 impl Renderable for Line {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
-        tag_writer.single_tag("path", Attributes::from([
-            ("d", &*make_line_path(self.start, self.end)),
-            ("fill", "none"),
-            ("stroke", self.color),
-            ("stroke-width", "2.0"),
-        ]))?;
-        tag_writer.single_tag("circle", Attributes::from([
-            ("cx", format!("{}", self.start.0).as_str()),
-            ("cy", format!("{}", self.start.1).as_str()),
-            ("r", &*CONNECT_DOT_RADIUS.to_string()),
-            ("fill", "#FFFFFF"),
-            ("stroke", "#000000"),
-            ("stroke-width", "1.0"),
-        ]))?;
-        tag_writer.single_tag("circle", Attributes::from([
-            ("cx", format!("{}", self.end.0).as_str()),
-            ("cy", format!("{}", self.end.1).as_str()),
-            ("r", &*CONNECT_DOT_RADIUS.to_string()),
-            ("fill", "#FFFFFF"),
-            ("stroke", "#000000"),
-            ("stroke-width", "1.0"),
-        ]))?;
-        Ok(())
+        self.shape().render(tag_writer)
     }
 }
 
@@ -106,22 +156,16 @@ impl Renderable for ConnectingLines {
 
 
 impl SvgPositioned for Line {
-    fn get_bbox(&self) -> Rect {
-        assert!(self.start.0 < self.end.0);
-        if self.end.1 >= self.start.0 {
-            Rect::new_ltrb(self.start.0, self.start.1, self.end.0, self.end.1)
-        } else {
-            Rect::new_ltrb(self.start.0, self.end.1, self.end.0, self.start.1)
-        }
+    fn get_bbox(&self) -> BBox {
+        self.shape().get_bbox()
     }
 }
 
 
 impl SvgPositioned for ConnectingLines {
-    fn get_bbox(&self) -> Rect {
+    fn get_bbox(&self) -> BBox {
         self.lines.iter()
             .map(|x| x.get_bbox())
-            .reduce(|r1, r2| r1.cover(&r2))
-            .unwrap_or(Rect::new_cwh((0.0, 0.0), 0.0, 0.0)) // FIXME: No way to say "don't cover anything" so this is what we'll do with no lines
+            .collect()
     }
 }
@@ -0,0 +1,135 @@
+//
+// This picks the top-to-bottom order of the surrounds so the connecting lines cross each
+// other as little as possible. The capabilities the lines start from are fixed in place,
+// so this is the "one-sided" version of the problem: start from the barycenter (average)
+// or median of where each surround's lines come from, then swap neighbors while that
+// helps.
+//
+
+use itertools::Itertools;
+use prog_draw::geometry::Coord;
+
+
+/// Most passes of neighbor swapping to make (each pass that changes nothing ends it early).
+const MAX_SWAP_PASSES: usize = 20;
+
+
+/// What minimize_crossings() decided.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossingOrder {
+    /// Indexes of the items, from top to bottom.
+    pub order: Vec<usize>,
+    /// Crossings if the items were simply sorted by their barycenter.
+    pub crossings_before: usize,
+    /// Crossings in the chosen order.
+    pub crossings_after: usize,
+}
+
+
+/// Returns the average of the values (items with no connections sort last).
+fn barycenter(ys: &[Coord]) -> Coord {
+    if ys.is_empty() {
+        Coord::INFINITY
+    } else {
+        ys.iter().sum::<Coord>() / ys.len() as Coord
+    }
+}
+
+/// Returns the median of the values (items with no connections sort last).
+fn median(ys: &[Coord]) -> Coord {
+    let sorted = ys.iter().copied().sorted_by(|a, b| a.total_cmp(b)).collect_vec();
+    match sorted.len() {
+        0 => Coord::INFINITY,
+        n if n % 2 == 1 => sorted[n / 2],
+        n => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+    }
+}
+
+/// Returns the order of the items when sorted by the key.
+fn sorted_order(connections: &[Vec<Coord>], key: fn(&[Coord]) -> Coord) -> Vec<usize> {
+    (0..connections.len())
+        .sorted_by(|i, j| key(&connections[*i]).total_cmp(&key(&connections[*j])))
+        .collect()
+}
+
+
+/// Returns the number of crossings between lines to an item placed above another and
+/// lines to that other item.
+fn pair_crossings(upper: &[Coord], lower: &[Coord]) -> usize {
+    upper.iter()
+        .map(|a| lower.iter().filter(|b| a > b).count())
+        .sum()
+}
+
+
+/// Returns how many times the lines cross if the items are placed in the given order.
+/// connections[i] holds the y positions of everything item i has lines to.
+pub fn count_crossings(connections: &[Vec<Coord>], order: &[usize]) -> usize {
+    let mut answer = 0;
+    for (pos, i) in order.iter().enumerate() {
+        for j in order[pos + 1..].iter() {
+            answer += pair_crossings(&connections[*i], &connections[*j]);
+        }
+    }
+    answer
+}
+
+
+/// Improves the order by swapping neighbors wherever that reduces crossings.
+fn swap_neighbors(connections: &[Vec<Coord>], order: &mut [usize]) {
+    for _ in 0..MAX_SWAP_PASSES {
+        let mut improved = false;
+        for pos in 0..order.len().saturating_sub(1) {
+            let (upper, lower) = (order[pos], order[pos + 1]);
+            let as_is = pair_crossings(&connections[upper], &connections[lower]);
+            let swapped = pair_crossings(&connections[lower], &connections[upper]);
+            if swapped < as_is {
+                order.swap(pos, pos + 1);
+                improved = true;
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+}
+
+
+/// Finds an order for the items that keeps the crossings low. connections[i] holds the
+/// y positions of everything item i has lines to.
+pub fn minimize_crossings(connections: &[Vec<Coord>]) -> CrossingOrder {
+    let barycenter_order = sorted_order(connections, barycenter);
+    let crossings_before = count_crossings(connections, &barycenter_order);
+
+    // --- try refining each of the starting orders, and keep the best ---
+    let mut best_order = barycenter_order.clone();
+    let mut best_crossings = crossings_before;
+    for mut order in [barycenter_order, sorted_order(connections, median)] {
+        swap_neighbors(connections, &mut order);
+        let crossings = count_crossings(connections, &order);
+        if crossings < best_crossings {
+            best_order = order;
+            best_crossings = crossings;
+        }
+    }
+    CrossingOrder{order: best_order, crossings_before, crossings_after: best_crossings}
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swaps_remove_crossings() {
+        // item 0 averages lower (on screen), but most of its lines come from the top
+        let connections = vec![
+            vec![0.0, 0.0, 30.0],
+            vec![5.0, 6.0],
+        ];
+        let result = minimize_crossings(&connections);
+        assert_eq!(result.crossings_before, 4);
+        assert_eq!(result.order, vec![0, 1]);
+        assert_eq!(result.crossings_after, 2);
+    }
+}
@@ -0,0 +1,140 @@
+//
+// Runs the prog_draw diagnostics over a laid-out document: it finds every pair of boxes
+// (capabilities, surrounds, titles, and the key) that overlap and every place where two
+// connecting lines cross.
+//
+
+use prog_draw::diagnostics::{Diagnostics, SceneBox, SceneLine};
+use prog_draw::geometry::Coord;
+use prog_draw::svg_render::SvgPositioned;
+use crate::capability_tree::CapabilityNodeTree;
+use crate::document::{TwoTreeViewDocument, TREE_SHIFT};
+
+
+/// Adds a box for each visible capability in the tree (except the root, which is hidden
+/// under the center dot).
+fn add_tree_boxes(boxes: &mut Vec<SceneBox>, tree: &CapabilityNodeTree, prefix: &str, dx: Coord) {
+    for placed in tree.placed_nodes() {
+        if placed.parent.is_some() {
+            boxes.push(SceneBox{
+                id: format!("{}_{}", prefix, placed.data.id),
+                kind: "capability",
+                rect: placed.bbox.translated(dx, 0.0),
+            });
+        }
+    }
+}
+
+
+/// Returns every box in the document, in the same coordinates as the SVG.
+pub fn scene_boxes(document: &TwoTreeViewDocument) -> Vec<SceneBox> {
+    let mut boxes: Vec<SceneBox> = Vec::new();
+    add_tree_boxes(&mut boxes, &document.core_tree, "core_cap", -TREE_SHIFT);
+    add_tree_boxes(&mut boxes, &document.surround_tree, "surround_cap", TREE_SHIFT);
+    let surrounds_dx = TREE_SHIFT + document.surrounds.x_position();
+    for surround in document.surrounds.iter() {
+        boxes.push(SceneBox{
+            id: format!("surround_{}", surround.id()),
+            kind: "surround",
+            rect: surround.box_rect().translated(surrounds_dx, 0.0),
+        });
+    }
+    for (title, label) in document.title_labels() {
+        if let Some(rect) = label.get_bbox().rect() {
+            boxes.push(SceneBox{id: title.to_string(), kind: "label", rect});
+        }
+    }
+    boxes.push(SceneBox{id: "key".to_string(), kind: "key", rect: document.legend_bbox()});
+    boxes
+}
+
+
+/// Returns every connecting line in the document, in the same coordinates as the SVG.
+pub fn scene_lines(document: &TwoTreeViewDocument) -> Vec<SceneLine> {
+    document.connecting_lines.iter()
+        .map(|line| SceneLine{
+            id: format!("{}->{}", line.capability_id(), line.surround_id()),
+            path: line.path().translated(TREE_SHIFT, 0.0),
+        })
+        .collect()
+}
+
+
+/// Checks the document for overlapping boxes and crossing connecting lines.
+pub fn diagnose(document: &TwoTreeViewDocument) -> Diagnostics {
+    Diagnostics::new(&scene_boxes(document), &scene_lines(document))
+}
+
+
+/// Returns a human-readable listing of what diagnose() finds.
+pub fn report(document: &TwoTreeViewDocument) -> String {
+    let diagnostics = diagnose(document);
+    let mut out = format!("{} overlapping boxes\n", diagnostics.overlaps.len());
+    for overlap in diagnostics.overlaps.iter() {
+        out.push_str(&format!(
+            "    {} {} overlaps {} {}\n",
+            overlap.first.kind, overlap.first.id, overlap.second.kind, overlap.second.id
+        ));
+    }
+    let (crossings_before, crossings_after) = document.surround_crossings();
+    out.push_str(&format!(
+        "{} line crossings (ordering the surrounds took it from {} to {})\n",
+        diagnostics.crossings.len(), crossings_before, crossings_after
+    ));
+    for crossing in diagnostics.crossings.iter() {
+        out.push_str(&format!(
+            "    {} crosses {} at ({}, {})\n",
+            crossing.first_id, crossing.second_id, crossing.point.0, crossing.point.1
+        ));
+    }
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use prog_draw::geometry::Rect;
+    use super::*;
+    use crate::spaced_layout::Spaceable;
+    use crate::test_support::tiny_document;
+
+    #[test]
+    fn test_scene_boxes_offsets() {
+        let document = tiny_document();
+        let boxes: HashMap<String, Rect> = scene_boxes(&document).into_iter()
+            .map(|scene_box| (scene_box.id, scene_box.rect))
+            .collect();
+        let bbox_in_tree = |tree: &CapabilityNodeTree, id: &str| tree.placed_nodes().iter()
+            .find(|placed| placed.data.id == id)
+            .unwrap()
+            .bbox;
+
+        // --- each tree is moved off the center, and the roots aren't included ---
+        assert_eq!(boxes["core_cap_C1a"], bbox_in_tree(&document.core_tree, "C1a").translated(-TREE_SHIFT, 0.0));
+        assert_eq!(boxes["surround_cap_C2"], bbox_in_tree(&document.surround_tree, "C2").translated(TREE_SHIFT, 0.0));
+        assert!(!boxes.contains_key("core_cap_ROOT") && !boxes.contains_key("surround_cap_ROOT"));
+
+        // --- the surrounds are moved past the surround tree ---
+        let surround = document.surrounds.get_by_id("SR1").unwrap();
+        let surrounds_dx = TREE_SHIFT + document.surrounds.x_position();
+        assert_eq!(boxes["surround_SR1"], surround.box_rect().translated(surrounds_dx, 0.0));
+        assert!(boxes["surround_SR1"].left() > boxes["surround_cap_C1b"].right());
+    }
+
+    #[test]
+    fn test_report() {
+        // --- the titles and key are clear of everything, so nothing is reported ---
+        let mut document = tiny_document();
+        assert_eq!(report(&document), "0 overlapping boxes\n0 line crossings (ordering the surrounds took it from 0 to 0)\n");
+
+        // --- put the second surround right on top of the first ---
+        let y = document.surrounds.get_by_id("SR1").unwrap().get_actual_y().unwrap();
+        document.surrounds.get_by_id_mut("SR2").unwrap().set_position(y);
+        assert_eq!(report(&document), "\
+1 overlapping boxes
+    surround surround_SR1 overlaps surround surround_SR2
+0 line crossings (ordering the surrounds took it from 0 to 0)
+");
+    }
+}
@@ -2,20 +2,28 @@
 // Support for document objects.
 //
 
-use std::collections::{HashMap, VecDeque};
-use prog_draw::data_tree::{DTNode, LAYOUT_DIRECTION, TreeLayoutDirection};
+use std::collections::HashMap;
+use itertools::Itertools;
+use prog_draw::data_tree::{EDGE_STYLE, LAYOUT_DIRECTION, TraversalOrder, TreeLayoutDirection};
 use prog_draw::geometry::Coord;
 use prog_draw::svg_writer::Renderable;
 use prog_draw::svg_writer::{TagWriterImpl, TagWriter, TagWriterError};
-use prog_draw::svg_render::{Group, Svg, SvgPositioned, Text};
-use prog_draw::geometry::Point;
-use crate::trifoil;
+use prog_draw::svg_render::{Group, OwnedGroup, Svg, SvgPositioned, SvgSize, Text};
+use prog_draw::hit_test::{hit_test, ElementRef, HitRegion};
+use prog_draw::layers::{Layer, LayeredScene};
+use prog_draw::layout::{Align, Anchor};
+use prog_draw::legend::Legend;
+use prog_draw::units::{Length, PageSize};
+use prog_draw::geometry::{Point, Rect, Transform};
+use crate::diagnostics;
+use crate::theme::{current_theme, set_current_theme, Theme};
+use crate::crossing_order;
 use crate::capability_db::CapabilitiesDB;
-use crate::capability_tree::{CapabilityData, CapabilityNodeTree, read_trees_from_capdb};
+use crate::capability_tree::{ANIMATE_TRANSITIONS, CapabilityData, CapabilityNodeTree, read_trees_from_capdb};
 use crate::center_dot::CenterDot;
 use crate::surrounds::{SurroundItem, SurroundItems};
 use crate::connecting_lines::ConnectingLines;
-use crate::used_by::UsedBySet;
+use crate::used_by::{used_by_legend, UsedBySet};
 
 
 pub const TEXT_ITEM_PADDING: Coord = 2.0;
@@ -24,13 +32,24 @@ pub const NODE_ITEM_ROUND_CORNER: Coord = 3.0;
 pub const CENTER_DOT_RADIUS: Coord = 40.0;
 pub const COLLAPSE_DOT_RADIUS: Coord = 3.0;
 pub const CONNECT_DOT_RADIUS: Coord = 2.0;
+pub const CONNECT_ARROW_SIZE: Coord = 8.0;
 pub const ITEM_SPACING: Coord = 8.0; // min vertical space between adjacent boxes
 pub const LAYER_SPACING: Coord = 16.0; // min horizontal space between layers in tree
 pub const SPACING_TO_SURROUNDS: Coord = 3.0 * LAYER_SPACING;
+pub const ORDER_NUDGE: Coord = 0.001; // keeps surrounds wanting the same spot in the chosen order
 pub const SVG_MARGIN: Coord = 4.0;
-pub const TRIFOIL_SCALE: Coord = 0.5;
-pub const TRIFOIL_MARGIN: Coord = 80.0;
+pub const PRINT_MARGIN_MM: Coord = 10.0;
+pub const LEGEND_MARGIN: Coord = 80.0;
 pub const TITLES_MARGIN: Coord = 30.0;
+pub const TREE_SHIFT: Coord = CENTER_DOT_RADIUS - 2.0 * TEXT_ITEM_PADDING; // how far each tree is moved from the center
+pub const CORE_TREE_TITLE: &str = "Core Capabilities";
+pub const SURROUND_TREE_TITLE: &str = "Surround Capabilities";
+pub const SURROUNDS_TITLE: &str = "Surrounds";
+pub const NODE_FONT_SIZE: Coord = 12.4; // FIXME: size for 14 and set this to 12.4 seems to work. WHY?
+pub const TITLE_FONT_SIZE: Coord = 28.0;
+pub const DOCUMENT_TITLE: &str = "Bank Core and Surround Capabilities";
+pub const DATASET_NAME: &str = "capabilities_db.xlsx";
+
 
 
 
@@ -41,12 +60,16 @@ pub struct TwoTreeViewDocument {
     pub surround_tree: CapabilityNodeTree,
     pub surrounds: SurroundItems,
     pub connecting_lines: ConnectingLines,
+    show_diagnostics: bool,
+    surround_crossings: (usize, usize),
+    /// When the SVG was generated (as an ISO 8601 UTC time), if it's known.
+    generated_at: Option<String>,
 }
 
 
 // FIXME: This part should be in the std library, right?
-struct WritableString {
-    s: String,
+pub struct WritableString {
+    pub s: String,
 }
 
 impl std::io::Write for WritableString {
@@ -73,7 +96,13 @@ impl TwoTreeViewDocument {
         let connecting_lines = Default::default();
 
         // --- create document ---
-        let mut doc = TwoTreeViewDocument{capdb, core_tree, surround_tree, surrounds, connecting_lines};
+        let show_diagnostics = false;
+        let surround_crossings = (0, 0);
+        let generated_at = None;
+        let mut doc = TwoTreeViewDocument{
+            capdb, core_tree, surround_tree, surrounds, connecting_lines, show_diagnostics, surround_crossings,
+            generated_at
+        };
 
         // --- perform layout ---
         doc.update_layout(true, true);
@@ -89,6 +118,35 @@ impl TwoTreeViewDocument {
         Ok(output.s)
     }
 
+    /// Returns the SVG scaled to fit on one of the known, named page sizes (see
+    /// PageSize::by_name()) turned sideways. If a name is passed in that isn't known,
+    /// this will panic.
+    ///
+    /// NOTE: It uses a string instead of an enum because it was designed to interact
+    ///   with JavaScript.
+    pub fn get_print_svg_str(&self, page_name: &str) -> Result<String,TagWriterError> {
+        let page = match PageSize::by_name(page_name) {
+            Some(page) => page.landscape(),
+            None => panic!("The name '{}' is not a known page size.", page_name),
+        };
+        let mut output: WritableString = WritableString {s:String::new()};
+        self.output_sized_to(&mut output, SvgSize::FitToPage{page, margin: Length::mm(PRINT_MARGIN_MM)})?;
+        Ok(output.s)
+    }
+
+    /// Returns the SVG at its natural size, centered on a canvas of one of the known,
+    /// named page sizes turned sideways. If a name is passed in that isn't known, this
+    /// will panic.
+    pub fn get_canvas_svg_str(&self, page_name: &str) -> Result<String,TagWriterError> {
+        let page = match PageSize::by_name(page_name) {
+            Some(page) => page.landscape(),
+            None => panic!("The name '{}' is not a known page size.", page_name),
+        };
+        let mut output: WritableString = WritableString {s:String::new()};
+        self.output_sized_to(&mut output, SvgSize::Canvas(page))?;
+        Ok(output.s)
+    }
+
     /// Returns the CapabilityData with that node_id if it exists; None if not.
     pub fn get_node_data(&self, id: &str) -> Option<&CapabilityData> {
         // NOTE: The tricky bit is that it could be in either tree (and we don't care which it's in)
@@ -104,33 +162,47 @@ impl TwoTreeViewDocument {
     }
 
     pub fn output_to(&self, output: &mut dyn std::io::Write) -> Result<(),TagWriterError> {
-        let shift_dist = CENTER_DOT_RADIUS - 2.0 * TEXT_ITEM_PADDING;
-
-
-        let core_tree_group = Group::item_transformed(&self.core_tree, Some((shift_dist * -1.0, 0.0)), None);
-        let surround_tree_group = Group::item_transformed(&self.surround_tree, Some((shift_dist, 0.0)), None);
-        let surrounds_group = Group::item_transformed(&self.surrounds, Some((shift_dist, 0.0)), None);
-        let connecting_lines_group = Group::item_transformed(&self.connecting_lines, Some((shift_dist, 0.0)), None);
-        let trifoil_group = Group::item_transformed(&trifoil::Trifoil, Some(self.trifoil_position()), Some(TRIFOIL_SCALE));
-        let core_cap_label = self.make_label("Core Capabilities", &core_tree_group);
-        let surround_cap_label = self.make_label("Surround Capabilities", &surround_tree_group);
-        let surrounds_label = self.make_label("Surrounds", &surrounds_group);
-
-        let content: [&dyn SvgPositioned; 9] = [
-            &trifoil_group,
-            &connecting_lines_group,
-            &core_tree_group,
-            &surround_tree_group,
-            &CenterDot,
-            &surrounds_group,
-            &core_cap_label,
-            &surround_cap_label,
-            &surrounds_label,
-        ];
-        let svg = Svg::new(Group::from(content), SVG_MARGIN);
+        self.output_sized_to(output, SvgSize::Unsized)
+    }
 
+    /// Returns everything that gets drawn, each on the layer it belongs on.
+    fn scene(&self) -> LayeredScene<'_> {
+        let shift_left = Transform::translate(-TREE_SHIFT, 0.0);
+        let shift_right = Transform::translate(TREE_SHIFT, 0.0);
+        let mut scene = LayeredScene::new();
+        scene.add_transformed(Layer::Edges, &self.connecting_lines, shift_right);
+        scene.add_layered(&self.core_tree, shift_left);
+        scene.add_layered(&self.surround_tree, shift_right);
+        scene.add(Layer::Nodes, CenterDot);
+        scene.add_transformed(Layer::Nodes, &self.surrounds, shift_right);
+        scene.add(Layer::Labels, self.legend_group());
+        scene.add(Layer::Labels, self.title_labels().into_iter().map(|(_, label)| label).collect::<Vec<Anchor>>());
+        if self.show_diagnostics {
+            scene.add(Layer::Overlay, diagnostics::diagnose(self));
+        }
+        scene
+    }
+
+    /// Writes the SVG, saying that it is the given size.
+    pub fn output_sized_to(&self, output: &mut dyn std::io::Write, size: SvgSize) -> Result<(),TagWriterError> {
+        let mut svg = Svg::new(self.scene(), SVG_MARGIN);
+        svg.set_size(size);
+        svg.set_background(current_theme().background);
+        svg.set_title(Some(DOCUMENT_TITLE));
+        svg.add_metadata("source", DATASET_NAME);
+        if let Some(generated_at) = &self.generated_at {
+            svg.add_metadata("date", generated_at);
+        }
+
+        // --- only the interactive drawing (which gets replaced after each change) is animated ---
+        let animate = size == SvgSize::Unsized;
+        let existing_animate = ANIMATE_TRANSITIONS.with(|it| it.replace(animate));
+        let existing_edge_style = EDGE_STYLE.with(|it| it.replace(current_theme().tree_edge_style()));
         let mut tag_writer = TagWriterImpl::new(output);
-        svg.render(&mut tag_writer)?;
+        let rendered = svg.render(&mut tag_writer);
+        EDGE_STYLE.with(|it| it.replace(existing_edge_style));
+        ANIMATE_TRANSITIONS.with(|it| it.set(existing_animate));
+        rendered?;
         tag_writer.close()?;
         Ok(())
     }
@@ -153,28 +225,22 @@ impl TwoTreeViewDocument {
     pub fn refold(&mut self, named_fold: &str) {
         match named_fold {
             "LEVEL_2" => {
-                fn apply_to_tree(node: &mut DTNode<CapabilityData>, depth: usize) {
-                    if depth < 2 {
-                        node.collapsed = false;
-                        for child in node.children.iter_mut() {
-                            apply_to_tree(child, depth + 1);
+                for tree in [&mut self.core_tree.tree, &mut self.surround_tree.tree] {
+                    for visit in tree.iter_mut() {
+                        match visit.depth {
+                            0 | 1 => *visit.collapsed = false,
+                            2 => *visit.collapsed = true,
+                            _ => {}, // hidden inside a collapsed node, so left as it is
                         }
-                    } else if depth == 2 {
-                        node.collapsed = true;
                     }
                 }
-                apply_to_tree(&mut self.core_tree.tree, 0);
-                apply_to_tree(&mut self.surround_tree.tree, 0);
             },
             "ALL_OPEN" => {
-                fn apply_to_tree(node: &mut DTNode<CapabilityData>) {
-                    node.collapsed = false;
-                    for child in node.children.iter_mut() {
-                        apply_to_tree(child);
+                for tree in [&mut self.core_tree.tree, &mut self.surround_tree.tree] {
+                    for visit in tree.iter_mut() {
+                        *visit.collapsed = false;
                     }
                 }
-                apply_to_tree(&mut self.core_tree.tree);
-                apply_to_tree(&mut self.surround_tree.tree);
             },
             _ => panic!("The name '{}' is not a known refold state.", named_fold)
         }
@@ -182,43 +248,146 @@ impl TwoTreeViewDocument {
     }
 
 
+    /// Call this once the drawing has been shown, so that drawing it again doesn't replay
+    /// the animations from the last change to the layout.
+    pub fn finish_transitions(&mut self) {
+        self.core_tree.remember_locations();
+        self.surround_tree.remember_locations();
+    }
+
     fn update_layout(&mut self, should_layout_core_tree: bool, should_layout_surround_tree: bool) {
         // FIXME: It would be better if the document maintained a needs_layout flag and
         //   performed the layout before returning svg.
+        // --- a tree that isn't laid out again stays still, so nothing in it gets animated ---
         if should_layout_core_tree {
             self.core_tree.layout();
+        } else {
+            self.core_tree.remember_locations();
         }
         if should_layout_surround_tree {
             self.surround_tree.layout();
             self.regenerate_connecting_lines();
+        } else {
+            self.surround_tree.remember_locations();
         }
     }
 
+    /// Switches to one of the built-in themes by name (see Theme::by_name()). If a name
+    /// is passed in that isn't known, this will panic.
+    ///
+    /// NOTE: It uses a string instead of an enum because it was designed to interact
+    ///   with JavaScript.
+    pub fn set_theme(&mut self, theme_name: &str) {
+        let theme = match Theme::by_name(theme_name) {
+            Some(theme) => theme,
+            None => panic!("The name '{}' is not a known theme.", theme_name),
+        };
+        set_current_theme(theme);
+        // --- the font (so the size of the text) and the colors of the lines may change ---
+        self.surrounds.remeasure_text();
+        self.update_layout(true, true);
+    }
+
+    /// Call this to turn on (or off) drawing the problems found by diagnostics::diagnose()
+    /// on top of the document.
+    pub fn set_show_diagnostics(&mut self, show_diagnostics: bool) {
+        self.show_diagnostics = show_diagnostics;
+    }
+
+    /// Call this to set the time (in milliseconds since 1970) that the SVGs record as when
+    /// they were generated.
+    pub fn set_generated_at(&mut self, millis_since_epoch: f64) {
+        self.generated_at = Some(iso_timestamp(millis_since_epoch));
+    }
+
+    /// Returns how many times the connecting lines would cross if the surrounds were just
+    /// placed at the average of what they connect to, and how many times they cross in
+    /// the order that was actually used. (Crossings are counted from the order alone, so
+    /// they may differ slightly from what diagnostics finds in the finished drawing.)
+    pub fn surround_crossings(&self) -> (usize, usize) {
+        self.surround_crossings
+    }
+
+    /// Returns the key, moved to where it is drawn.
+    fn legend_group(&self) -> OwnedGroup<'static> {
+        let legend = used_by_legend();
+        let (x, y) = self.legend_position(&legend);
+        OwnedGroup::item_with_transform(legend, Transform::translate(x, y))
+    }
+
+    /// Returns the area the key is drawn in.
+    pub fn legend_bbox(&self) -> Rect {
+        self.legend_group().get_bbox().rect().unwrap() // the key is never empty
+    }
+
+    /// Returns everything a pointer can land on (the capabilities and surrounds), from back
+    /// to front and in the same coordinates as the SVG.
+    pub fn hit_regions(&self) -> Vec<HitRegion> {
+        let mut regions: Vec<HitRegion> = Vec::new();
+        for (tree, dx) in [(&self.core_tree, -TREE_SHIFT), (&self.surround_tree, TREE_SHIFT)] {
+            for placed in tree.placed_nodes() {
+                if placed.parent.is_some() { // the root is hidden under the center dot
+                    let element = ElementRef::new("capability", &placed.data.id);
+                    regions.push(HitRegion{element, bbox: placed.bbox.translated(dx, 0.0).into()});
+                }
+            }
+        }
+        let surrounds_dx = TREE_SHIFT + self.surrounds.x_position();
+        for surround in self.surrounds.iter() {
+            let element = ElementRef::new("surround", surround.id());
+            regions.push(HitRegion{element, bbox: surround.get_bbox().translated(surrounds_dx, 0.0)});
+        }
+        regions
+    }
+
+    /// Returns the capability or surround drawn at the point (in the same coordinates as
+    /// the SVG), or None if there isn't one.
+    #[allow(dead_code)] // this IS used, but from javascript
+    pub fn node_at(&self, point: Point) -> Option<ElementRef> {
+        hit_test(&self.hit_regions(), point)
+    }
+
+    /// Returns the titles drawn over each part of the document, along with the title text.
+    /// Parts that are empty don't get a title.
+    pub fn title_labels(&self) -> Vec<(&'static str, Anchor<'static>)> {
+        let core_tree_group = Group::item_transformed(&self.core_tree, Some((-TREE_SHIFT, 0.0)), None);
+        let surround_tree_group = Group::item_transformed(&self.surround_tree, Some((TREE_SHIFT, 0.0)), None);
+        let surrounds_group = Group::item_transformed(&self.surrounds, Some((TREE_SHIFT, 0.0)), None);
+        [
+            (CORE_TREE_TITLE, &core_tree_group),
+            (SURROUND_TREE_TITLE, &surround_tree_group),
+            (SURROUNDS_TITLE, &surrounds_group),
+        ].into_iter()
+            .filter_map(|(title, group)| self.make_label(title, group).map(|label| (title, label)))
+            .collect()
+    }
+
     /// This finds a good place to put the key. It returns an (x,y) offset from the center
     /// that would be good to move it to.
-    fn trifoil_position(&self) -> Point {
-        let trifoil_bbox = trifoil::Trifoil.get_bbox().scaled_about_center(TRIFOIL_SCALE);
-        let left_top = self.core_tree.get_bbox().top();
-        let right_top = self.surround_tree.get_bbox().top();
+    fn legend_position(&self, legend: &Legend) -> Point {
+        let legend_bbox = legend.get_bbox().rect().unwrap(); // the key is never empty
+        let left_top = self.core_tree.box_rect().top();
+        let right_top = self.surround_tree.box_rect().top();
         let best_top = left_top.max(right_top);
-        let y_position = best_top + -trifoil_bbox.bottom() - TRIFOIL_MARGIN;
+        let y_position = best_top + -legend_bbox.bottom() - LEGEND_MARGIN;
         let x_position = if left_top <= right_top {
-            -trifoil_bbox.left() + TRIFOIL_MARGIN
+            -legend_bbox.left() + LEGEND_MARGIN
         } else {
-            trifoil_bbox.left() - TRIFOIL_MARGIN
+            -legend_bbox.right() - LEGEND_MARGIN
         };
         (x_position, y_position)
     }
 
 
     /// This creates a label with the given text appearing centered over the given target.
-    fn make_label(&self, text: &str, target: &dyn SvgPositioned) -> Text {
-        let bbox = target.get_bbox();
-        Text::new_styled(
-            text,
-            (bbox.center_x(), bbox.top() - TITLES_MARGIN),
-            Some("Arial".to_string()),
-            Some("28px".to_string())
+    /// If the target is empty there is nothing to label, so it returns None.
+    fn make_label(&self, text: &str, target: &dyn SvgPositioned) -> Option<Anchor<'static>> {
+        Anchor::relative_to(
+            Text::with_style(text, (0.0, 0.0), current_theme().title_style()),
+            (Align::Center, Align::Center),
+            target,
+            (Align::Center, Align::Start),
+            (0.0, -TITLES_MARGIN),
         )
     }
 
@@ -233,6 +402,7 @@ impl TwoTreeViewDocument {
         // This is the list of connections from capabilities to surrounds. We'll use it for layout,
         // then for making lines.
         struct Connection {
+            capability_id: String,
             capability_pos: Point,
             surround_id: String,
             used_by_set: UsedBySet
@@ -240,34 +410,27 @@ impl TwoTreeViewDocument {
 
         let mut connections: Vec<Connection> = Vec::new();
 
-        // we'll use iteration instead of recursion, so here's our stack
-        let mut node_stack: VecDeque<&DTNode<CapabilityData>> = VecDeque::new();
-        node_stack.push_back(&self.surround_tree.tree);
-        while !node_stack.is_empty() {
-            let node = node_stack.pop_front().unwrap();
-            if node.children.is_empty() || node.collapsed {
-                // --- it's "leaf" (as visible on the screen now) ---
-                for (surround_name, used_by_set) in self.capdb.get_related_surrounds(&node.data.id) {
-                    match self.surrounds.get_by_name(surround_name) {
-                        None => {
-                            if !surround_name.starts_with("TODO: ") {
-                                // FIXME: A better way to report this might be nice; this mostly just ignores bad data
-                                println!("Could not find a surround named '{}' which is mentioned in {}. Skipped.", surround_name, node.data.id);
-                            }
-                        },
-                        Some(surround_item) => {
-                            let capability_bbox = node.get_bbox();
-                            let capability_pos: Point = (capability_bbox.right(), capability_bbox.center_y());
-                            let surround_id = surround_item.id().to_string();
-                            connections.push(Connection{capability_pos, surround_id, used_by_set});
+        // --- only the "leaves" (as visible on the screen now) get connected ---
+        let visible_leaves = self.surround_tree.tree.traverse(TraversalOrder::BreadthFirst, true)
+            .map(|visit| visit.node)
+            .filter(|node| node.children.is_empty() || node.collapsed);
+        for node in visible_leaves {
+            for (surround_name, used_by_set) in self.capdb.get_related_surrounds(&node.data.id) {
+                match self.surrounds.get_by_name(surround_name) {
+                    None => {
+                        if !surround_name.starts_with("TODO: ") {
+                            // FIXME: A better way to report this might be nice; this mostly just ignores bad data
+                            println!("Could not find a surround named '{}' which is mentioned in {}. Skipped.", surround_name, node.data.id);
                         }
+                    },
+                    Some(surround_item) => {
+                        let capability_bbox = node.data.box_rect();
+                        let capability_pos: Point = (capability_bbox.right(), capability_bbox.center_y());
+                        let capability_id = node.data.id.clone();
+                        let surround_id = surround_item.id().to_string();
+                        connections.push(Connection{capability_id, capability_pos, surround_id, used_by_set});
                     }
                 }
-            } else{
-                // --- not a "leaf" so "recurse" ---
-                for child in node.children.iter() {
-                    node_stack.push_back(child)
-                }
             }
         }
 
@@ -284,21 +447,37 @@ impl TwoTreeViewDocument {
             }
         }
 
-        // Now we can position each surround vertically
+        // Pick an order for the surrounds that keeps the lines from crossing much
+        let surround_ids: Vec<&String> = connections_by_surround_name.keys().copied().sorted().collect();
+        let connected_ys: Vec<Vec<Coord>> = surround_ids.iter()
+            .map(|id| connections_by_surround_name[id].iter().map(|connection| connection.capability_pos.1).collect())
+            .collect();
+        let crossing_order = crossing_order::minimize_crossings(&connected_ys);
+
+        // Now we can position each surround vertically, at the average of the things it's
+        // connected to. If a better order was found, those spots are handed out in that
+        // order instead (each nudged below the one before so distribute_space() keeps it).
+        let average_ys: Vec<Coord> = connected_ys.iter()
+            .map(|ys| ys.iter().sum::<Coord>() / (ys.len() as Coord))
+            .collect();
         let mut desired_surround_positions: HashMap<String, Coord> = HashMap::new();
-        for (id, connection_list) in connections_by_surround_name.iter() {
-            assert!(connection_list.len() > 0);
-
-            // Average the things it's connected to to find a y value
-            let sum_of_y_values: Coord = connection_list.iter()
-                .map(|connection| connection.capability_pos.1)
-                .sum();
-            let average_y = sum_of_y_values / (connection_list.len() as Coord);
-            desired_surround_positions.insert(id.to_string(), average_y);
+        if crossing_order.crossings_after < crossing_order.crossings_before {
+            let sorted_ys = average_ys.iter().copied().sorted_by(|a, b| a.total_cmp(b));
+            let mut previous_y = Coord::NEG_INFINITY;
+            for (idx, average_y) in crossing_order.order.iter().zip(sorted_ys) {
+                let y = average_y.max(previous_y + ORDER_NUDGE);
+                desired_surround_positions.insert(surround_ids[*idx].to_string(), y);
+                previous_y = y;
+            }
+        } else {
+            for (id, average_y) in surround_ids.iter().zip(average_ys) {
+                desired_surround_positions.insert(id.to_string(), average_y);
+            }
         }
+        self.surround_crossings = (crossing_order.crossings_before, crossing_order.crossings_after);
 
         // Decide where to position the surrounds in the x direction
-        let surround_x = self.surround_tree.get_bbox().right() + SPACING_TO_SURROUNDS;
+        let surround_x = self.surround_tree.box_rect().right() + SPACING_TO_SURROUNDS;
 
         // Now move the actual surrounds
         self.surrounds.reset_positions(surround_x);
@@ -311,10 +490,51 @@ impl TwoTreeViewDocument {
         self.connecting_lines.clear();
         for connection in connections.iter() {
             let surround_y = self.surrounds.get_by_id_mut(&connection.surround_id).unwrap().get_actual_y().unwrap();
-            self.connecting_lines.add_line(connection.capability_pos, (surround_x, surround_y), &connection.used_by_set);
+            self.connecting_lines.add_line(
+                &connection.capability_id,
+                &connection.surround_id,
+                connection.capability_pos,
+                (surround_x, surround_y),
+                &connection.used_by_set
+            );
         }
 
         // Now that we're done, restore the tree direction
         LAYOUT_DIRECTION.with(|it| it.set(existing_direction));
     }
 }
+
+
+/// Returns the time (given in milliseconds since 1970 began, UTC) in ISO 8601 form, like
+/// "2024-02-29T13:05:09Z".
+fn iso_timestamp(millis_since_epoch: f64) -> String {
+    let secs = (millis_since_epoch / 1000.0).floor() as i64;
+    let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    // --- turn days into a date (see http://howardhinnant.github.io/date_algorithms.html) ---
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 {mp + 3} else {mp - 9};
+    let year = year_of_era + era * 400 + if month <= 2 {1} else {0};
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso_timestamp() {
+        assert_eq!(iso_timestamp(0.0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso_timestamp(1709211909000.0), "2024-02-29T13:05:09Z");
+        assert_eq!(iso_timestamp(-1000.0), "1969-12-31T23:59:59Z");
+    }
+}
@@ -0,0 +1,336 @@
+//
+// Exports the laid-out document as a draw.io (mxGraph XML) file. Every shape keeps the
+// position and colors it has in the SVG, and every line is an edge that is attached to
+// the shapes at its ends, so the diagram can be hand-tweaked in diagrams.net.
+//
+
+use std::collections::HashMap;
+use prog_draw::data_tree::{child_line_points, TreeLayoutDirection};
+use prog_draw::geometry::{BBox, Coord, Point, Rect};
+use prog_draw::svg_writer::{Attributes, TagWriter, TagWriterError, TagWriterImpl};
+use crate::capability_tree::CapabilityNodeTree;
+use crate::document::{
+    TwoTreeViewDocument, WritableString, CENTER_DOT_RADIUS, NODE_FONT_SIZE,
+    NODE_ITEM_ROUND_CORNER, SVG_MARGIN, TREE_SHIFT
+};
+use crate::theme::current_theme;
+use crate::used_by::get_color_strs;
+
+
+/// A shape, in the coordinates of the SVG.
+struct Vertex {
+    id: String,
+    value: String,
+    style: String,
+    bbox: Rect,
+}
+
+/// A line between two shapes, which bends through the points (in the coordinates of the
+/// SVG) on its way.
+struct Edge {
+    style: String,
+    source: String,
+    target: String,
+    points: Vec<Point>,
+}
+
+
+/// Returns the id used for a capability in one of the trees.
+fn capability_cell_id(prefix: &str, capability_id: &str) -> String {
+    format!("{}_{}", prefix, capability_id)
+}
+
+/// Returns the id used for a surround.
+fn surround_cell_id(surround_id: &str) -> String {
+    format!("surround_{}", surround_id)
+}
+
+/// Returns the style used for a rounded box, outlined and lettered the way the theme
+/// draws it in the SVG.
+fn box_style(fill_color: &str, font_color: &str) -> String {
+    let theme = current_theme();
+    format!(
+        "rounded=1;absoluteArcSize=1;arcSize={};whiteSpace=nowrap;spacing=0;fillColor={};strokeColor={};strokeWidth={};fontColor={};fontFamily={};fontSize={};",
+        2.0 * NODE_ITEM_ROUND_CORNER, fill_color, theme.outline, theme.outline_width, font_color,
+        theme.font_family, NODE_FONT_SIZE
+    )
+}
+
+/// Returns the style used for an edge that leaves its source on one side and enters its
+/// target on the other. The edge is curved through its points rather than routed by
+/// draw.io, so it follows the same path as in the SVG.
+fn edge_style(direction: TreeLayoutDirection, stroke_color: &str, stroke_width: Coord) -> String {
+    let (exit_x, entry_x) = match direction {
+        TreeLayoutDirection::Right => (1, 0),
+        TreeLayoutDirection::Left => (0, 1),
+    };
+    format!(
+        "curved=1;endArrow=none;html=0;strokeColor={};strokeWidth={};exitX={};exitY=0.5;exitDx=0;exitDy=0;entryX={};entryY=0.5;entryDx=0;entryDy=0;",
+        stroke_color, stroke_width, exit_x, entry_x
+    )
+}
+
+
+/// Adds the visible nodes of one of the trees (except the root, which sits under the
+/// center dot) and the edges between them.
+fn add_tree(vertices: &mut Vec<Vertex>, edges: &mut Vec<Edge>, tree: &CapabilityNodeTree, prefix: &str, dx: Coord) {
+    let direction = tree.layout_direction();
+    let placed_nodes = tree.placed_nodes();
+    let bboxes: HashMap<&str, Rect> = placed_nodes.iter()
+        .map(|placed| (placed.data.id.as_str(), placed.bbox))
+        .collect();
+    for placed in placed_nodes.iter() {
+        let parent = match placed.parent {
+            None => continue, // the root
+            Some(parent) => parent,
+        };
+        let id = capability_cell_id(prefix, &placed.data.id);
+        let (box_color, text_color) = get_color_strs(&placed.data.used_by_set);
+        vertices.push(Vertex{
+            id: id.clone(),
+            value: placed.data.text.clone(),
+            style: box_style(box_color, text_color),
+            bbox: placed.bbox.translated(dx, 0.0),
+        });
+        let source = if parent.id == tree.tree.data.id {
+            "center".to_string()
+        } else {
+            capability_cell_id(prefix, &parent.id)
+        };
+        let [_, parent_ctrl, child_ctrl, _] = child_line_points(&bboxes[parent.id.as_str()], &placed.bbox, direction);
+        let points = vec![(parent_ctrl.0 + dx, parent_ctrl.1), (child_ctrl.0 + dx, child_ctrl.1)];
+        let theme = current_theme();
+        edges.push(Edge{style: edge_style(direction, theme.tree_edge, theme.outline_width), source, target: id, points});
+    }
+}
+
+
+/// Returns the document as the contents of a .drawio file.
+pub fn drawio_xml(document: &TwoTreeViewDocument) -> Result<String, TagWriterError> {
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut edges: Vec<Edge> = Vec::new();
+
+    // --- the center dot ---
+    vertices.push(Vertex{
+        id: "center".to_string(),
+        value: "".to_string(),
+        style: format!("ellipse;fillColor={};strokeColor=none;", current_theme().outline),
+        bbox: Rect::new_cwh((0.0, 0.0), 2.0 * CENTER_DOT_RADIUS, 2.0 * CENTER_DOT_RADIUS),
+    });
+
+    // --- the trees ---
+    add_tree(&mut vertices, &mut edges, &document.core_tree, "core_cap", -TREE_SHIFT);
+    add_tree(&mut vertices, &mut edges, &document.surround_tree, "surround_cap", TREE_SHIFT);
+
+    // --- the surrounds ---
+    let surrounds_dx = TREE_SHIFT + document.surrounds.x_position();
+    for surround in document.surrounds.iter() {
+        let (box_color, text_color) = get_color_strs(surround.used_by_set());
+        let mut style = box_style(box_color, text_color);
+        if surround.data.is_new_system {
+            style.push_str("shape=ext;double=1;");
+        }
+        vertices.push(Vertex{
+            id: surround_cell_id(surround.id()),
+            value: surround.data.name.clone(),
+            style,
+            bbox: surround.box_rect().translated(surrounds_dx, 0.0),
+        });
+    }
+
+    // --- the connecting lines ---
+    for line in document.connecting_lines.iter() {
+        let (line_color, _) = get_color_strs(line.used_by_set());
+        let (start_ctrl, end_ctrl) = line.control_points();
+        edges.push(Edge{
+            style: edge_style(TreeLayoutDirection::Right, line_color, 2.0),
+            source: capability_cell_id("surround_cap", line.capability_id()),
+            target: surround_cell_id(line.surround_id()),
+            points: vec![(start_ctrl.0 + TREE_SHIFT, start_ctrl.1), (end_ctrl.0 + TREE_SHIFT, end_ctrl.1)],
+        });
+    }
+
+    // --- move everything so it starts near (0,0) ---
+    let covering = vertices.iter()
+        .map(|x| BBox::from(x.bbox))
+        .collect::<BBox>()
+        .rect()
+        .unwrap(); // there is always a center dot
+    let dx = SVG_MARGIN - covering.left();
+    let dy = SVG_MARGIN - covering.top();
+
+    // --- write it out ---
+    let mut output: WritableString = WritableString {s:String::new()};
+    let mut tag_writer = TagWriterImpl::new(&mut output);
+    tag_writer.begin_tag("mxfile", Attributes::from([("host", "prog_draw")]))?;
+    tag_writer.begin_tag("diagram", Attributes::from([("id", "capabilities"), ("name", "Capabilities")]))?;
+    tag_writer.begin_tag("mxGraphModel", Attributes::from([
+        ("grid", "0"),
+        ("page", "0"),
+        ("math", "0"),
+        ("shadow", "0"),
+    ]))?;
+    tag_writer.begin_tag("root", Attributes::new())?;
+    tag_writer.single_tag("mxCell", Attributes::from([("id", "0")]))?;
+    tag_writer.single_tag("mxCell", Attributes::from([("id", "1"), ("parent", "0")]))?;
+    for vertex in vertices.iter() {
+        let bbox = vertex.bbox.translated(dx, dy);
+        tag_writer.begin_tag("mxCell", Attributes::from([
+            ("id", vertex.id.as_str()),
+            ("value", vertex.value.as_str()),
+            ("style", vertex.style.as_str()),
+            ("vertex", "1"),
+            ("parent", "1"),
+        ]))?;
+        tag_writer.single_tag("mxGeometry", Attributes::from([
+            ("x", bbox.left().to_string()),
+            ("y", bbox.top().to_string()),
+            ("width", bbox.width().to_string()),
+            ("height", bbox.height().to_string()),
+            ("as", "geometry".to_string()),
+        ]))?;
+        tag_writer.end_tag("mxCell")?;
+    }
+    for (i, edge) in edges.iter().enumerate() {
+        tag_writer.begin_tag("mxCell", Attributes::from([
+            ("id", format!("edge_{}", i).as_str()),
+            ("style", edge.style.as_str()),
+            ("edge", "1"),
+            ("parent", "1"),
+            ("source", edge.source.as_str()),
+            ("target", edge.target.as_str()),
+        ]))?;
+        tag_writer.begin_tag("mxGeometry", Attributes::from([("relative", "1"), ("as", "geometry")]))?;
+        tag_writer.begin_tag("Array", Attributes::from([("as", "points")]))?;
+        for point in edge.points.iter() {
+            tag_writer.single_tag("mxPoint", Attributes::from([
+                ("x", point.0 + dx),
+                ("y", point.1 + dy),
+            ]))?;
+        }
+        tag_writer.end_tag("Array")?;
+        tag_writer.end_tag("mxGeometry")?;
+        tag_writer.end_tag("mxCell")?;
+    }
+    tag_writer.end_tag("root")?;
+    tag_writer.end_tag("mxGraphModel")?;
+    tag_writer.end_tag("diagram")?;
+    tag_writer.end_tag("mxfile")?;
+    tag_writer.close()?;
+    Ok(output.s)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::tiny_document;
+
+    /// Returns the value of an attribute in a line of the XML.
+    fn attribute<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+        let start = line.find(&format!(" {}=\"", name))? + name.len() + 3;
+        let length = line[start..].find('"')?;
+        Some(&line[start..start + length])
+    }
+
+    /// Returns the rect in an mxGeometry line.
+    fn geometry(line: &str) -> Rect {
+        let field = |name| attribute(line, name).unwrap().parse::<Coord>().unwrap();
+        Rect::new_ltwh(field("x"), field("y"), field("width"), field("height"))
+    }
+
+    /// Returns the points an edge bends through, which are listed after its cell.
+    fn waypoints(lines: &[&str], edge_id: &str) -> Vec<Point> {
+        let start = lines.iter()
+            .position(|line| line.starts_with("<mxCell ") && attribute(line, "id") == Some(edge_id))
+            .unwrap();
+        lines[start..].iter()
+            .take_while(|line| **line != "</mxCell>")
+            .filter(|line| line.starts_with("<mxPoint "))
+            .map(|line| (attribute(line, "x").unwrap().parse().unwrap(), attribute(line, "y").unwrap().parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_drawio_xml() {
+        let document = tiny_document();
+        let xml = drawio_xml(&document).unwrap();
+        let lines: Vec<&str> = xml.lines().map(|line| line.trim()).collect();
+        // (each cell is followed by its mxGeometry)
+        let cells: Vec<(&str, &str)> = lines.iter().zip(lines.iter().skip(1))
+            .filter(|(line, _)| line.starts_with("<mxCell ") && attribute(line, "parent") == Some("1"))
+            .map(|(line, next)| (*line, *next))
+            .collect();
+        let vertices: HashMap<&str, (&str, Rect)> = cells.iter()
+            .filter(|(line, _)| attribute(line, "vertex").is_some())
+            .map(|(line, next)| (attribute(line, "id").unwrap(), (*line, geometry(next))))
+            .collect();
+        let edges: Vec<&str> = cells.iter()
+            .filter(|(line, _)| attribute(line, "edge").is_some())
+            .map(|(line, _)| *line)
+            .collect();
+
+        // --- the center, 6 capabilities, and 2 surrounds; and a line to each capability and surround ---
+        let mut vertex_ids: Vec<&str> = vertices.keys().copied().collect();
+        vertex_ids.sort();
+        assert_eq!(vertex_ids, vec![
+            "center", "core_cap_C1", "core_cap_C1a", "core_cap_C1b",
+            "surround_SR1", "surround_SR2", "surround_cap_C1", "surround_cap_C1b", "surround_cap_C2",
+        ]);
+        assert_eq!(edges.len(), 8);
+        for edge in edges.iter() {
+            assert!(vertices.contains_key(attribute(edge, "source").unwrap()), "{}", edge);
+            assert!(vertices.contains_key(attribute(edge, "target").unwrap()), "{}", edge);
+        }
+
+        // --- everything is moved by the same amount, so it starts at the margin ---
+        let center = vertices["center"].1;
+        let (dx, dy) = (center.left() + CENTER_DOT_RADIUS, center.top() + CENTER_DOT_RADIUS);
+        let left = vertices.values().map(|(_, rect)| rect.left()).fold(Coord::INFINITY, Coord::min);
+        let top = vertices.values().map(|(_, rect)| rect.top()).fold(Coord::INFINITY, Coord::min);
+        assert_eq!((left, top), (SVG_MARGIN, SVG_MARGIN));
+
+        // --- a capability, with its label escaped ---
+        let placed = document.core_tree.placed_nodes().into_iter()
+            .find(|placed| placed.data.id == "C1a")
+            .unwrap();
+        let (cell, rect) = vertices["core_cap_C1a"];
+        assert_eq!(attribute(cell, "value"), Some("Loans &amp; &lt;Notes>"));
+        let (box_color, text_color) = get_color_strs(&placed.data.used_by_set);
+        assert_eq!(attribute(cell, "style"), Some(box_style(box_color, text_color).as_str()));
+        assert_eq!(rect, placed.bbox.translated(dx - TREE_SHIFT, dy));
+
+        // --- a surround ---
+        let surround = document.surrounds.get_by_id("SR1").unwrap();
+        let surrounds_dx = TREE_SHIFT + document.surrounds.x_position();
+        assert_eq!(vertices["surround_SR1"].1, surround.box_rect().translated(dx + surrounds_dx, dy));
+
+        // --- a tree edge, which bends through the same control points as in the SVG ---
+        assert!(edges.contains(&format!(
+            r#"<mxCell id="edge_1" style="{}" edge="1" parent="1" source="core_cap_C1" target="core_cap_C1a">"#,
+            edge_style(TreeLayoutDirection::Left, "#000000", 1.0)
+        ).as_str()));
+        let parent = document.core_tree.placed_nodes().into_iter()
+            .find(|placed| placed.data.id == "C1")
+            .unwrap();
+        let [_, parent_ctrl, child_ctrl, _] = child_line_points(&parent.bbox, &placed.bbox, TreeLayoutDirection::Left);
+        assert_eq!(waypoints(&lines, "edge_1"), vec![
+            (parent_ctrl.0 + dx - TREE_SHIFT, parent_ctrl.1 + dy),
+            (child_ctrl.0 + dx - TREE_SHIFT, child_ctrl.1 + dy),
+        ]);
+
+        // --- a connecting line ---
+        let edge = edges.iter()
+            .find(|edge| attribute(edge, "source") == Some("surround_cap_C1b") && attribute(edge, "target") == Some("surround_SR1"))
+            .unwrap();
+        assert!(attribute(edge, "style").unwrap().contains("strokeWidth=2;"));
+        let line = document.connecting_lines.iter()
+            .find(|line| line.capability_id() == "C1b" && line.surround_id() == "SR1")
+            .unwrap();
+        let (start_ctrl, end_ctrl) = line.control_points();
+        assert_eq!(waypoints(&lines, attribute(edge, "id").unwrap()), vec![
+            (start_ctrl.0 + dx + TREE_SHIFT, start_ctrl.1 + dy),
+            (end_ctrl.0 + dx + TREE_SHIFT, end_ctrl.1 + dy),
+        ]);
+    }
+}
@@ -0,0 +1,266 @@
+//
+// Exports the laid-out document as a JSON scene graph: every node with its position and
+// styling, and every line with the points of its curve. Other front ends can draw the
+// layout from this without needing to parse the SVG.
+//
+
+use std::collections::HashMap;
+use serde::Serialize;
+use prog_draw::data_tree::child_line_points;
+use prog_draw::geometry::{BBox, Coord, Point, Rect};
+use prog_draw::svg_render::SvgPositioned;
+use crate::capability_tree::CapabilityNodeTree;
+use crate::document::{TwoTreeViewDocument, CENTER_DOT_RADIUS, TREE_SHIFT};
+use crate::theme::current_theme;
+use crate::used_by::{get_color_strs, UsedBySet};
+
+
+#[derive(Serialize, Debug)]
+struct JsonRect {
+    left: Coord,
+    top: Coord,
+    width: Coord,
+    height: Coord,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonUsedBySet {
+    consumer: &'static str,
+    sbb: &'static str,
+    commercial: &'static str,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonNode<'a> {
+    id: String,
+    data_id: &'a str,
+    kind: &'static str,
+    parent: Option<String>,
+    label: &'a str,
+    bbox: JsonRect,
+    style_class: &'static str,
+    fill_color: &'static str,
+    text_color: &'static str,
+    collapsed: bool,
+    used_by: JsonUsedBySet,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonLine {
+    kind: &'static str,
+    source: String,
+    target: String,
+    start: Point,
+    control_points: [Point; 2],
+    end: Point,
+    color: &'static str,
+    used_by: Option<JsonUsedBySet>,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonScene<'a> {
+    bbox: JsonRect,
+    nodes: Vec<JsonNode<'a>>,
+    lines: Vec<JsonLine>,
+}
+
+
+impl From<Rect> for JsonRect {
+    fn from(rect: Rect) -> Self {
+        JsonRect{left: rect.left(), top: rect.top(), width: rect.width(), height: rect.height()}
+    }
+}
+
+impl From<&UsedBySet> for JsonUsedBySet {
+    fn from(used_by_set: &UsedBySet) -> Self {
+        JsonUsedBySet{
+            consumer: used_by_set.consumer.into(),
+            sbb: used_by_set.sbb.into(),
+            commercial: used_by_set.commercial.into(),
+        }
+    }
+}
+
+
+/// Returns the point moved by (dx, 0).
+fn shifted(point: Point, dx: Coord) -> Point {
+    (point.0 + dx, point.1)
+}
+
+
+/// Adds the visible nodes of one of the trees and the lines between them.
+fn add_tree<'a>(
+    nodes: &mut Vec<JsonNode<'a>>,
+    lines: &mut Vec<JsonLine>,
+    tree: &'a CapabilityNodeTree,
+    prefix: &str,
+    dx: Coord
+) {
+    let direction = tree.layout_direction();
+    let mut bboxes: HashMap<&str, Rect> = HashMap::new(); // the parents come before the children
+    for placed in tree.placed_nodes() {
+        bboxes.insert(&placed.data.id, placed.bbox);
+        let id = format!("{}_{}", prefix, placed.data.id);
+        let (fill_color, text_color) = get_color_strs(&placed.data.used_by_set);
+        let parent = placed.parent.map(|parent| format!("{}_{}", prefix, parent.id));
+        if let Some(parent_data) = placed.parent {
+            let parent_bbox = bboxes[parent_data.id.as_str()];
+            let [start, ctrl_1, ctrl_2, end] = child_line_points(&parent_bbox, &placed.bbox, direction);
+            lines.push(JsonLine{
+                kind: "tree",
+                source: format!("{}_{}", prefix, parent_data.id),
+                target: id.clone(),
+                start: shifted(start, dx),
+                control_points: [shifted(ctrl_1, dx), shifted(ctrl_2, dx)],
+                end: shifted(end, dx),
+                color: current_theme().tree_edge,
+                used_by: None,
+            });
+        }
+        nodes.push(JsonNode{
+            id,
+            data_id: &placed.data.id,
+            kind: "capability",
+            parent,
+            label: &placed.data.text,
+            bbox: placed.bbox.translated(dx, 0.0).into(),
+            style_class: placed.data.style_class(),
+            fill_color,
+            text_color,
+            collapsed: placed.collapsed,
+            used_by: (&placed.data.used_by_set).into(),
+        });
+    }
+}
+
+
+/// Returns the laid-out document as a JSON scene graph. Everything is in the same
+/// coordinates as the SVG.
+pub fn scene_json(document: &TwoTreeViewDocument) -> Result<String, serde_json::Error> {
+    let mut nodes: Vec<JsonNode> = Vec::new();
+    let mut lines: Vec<JsonLine> = Vec::new();
+
+    // --- the trees ---
+    add_tree(&mut nodes, &mut lines, &document.core_tree, "core_cap", -TREE_SHIFT);
+    add_tree(&mut nodes, &mut lines, &document.surround_tree, "surround_cap", TREE_SHIFT);
+
+    // --- the surrounds ---
+    let surrounds_dx = TREE_SHIFT + document.surrounds.x_position();
+    for surround in document.surrounds.iter() {
+        let (fill_color, text_color) = get_color_strs(surround.used_by_set());
+        nodes.push(JsonNode{
+            id: format!("surround_{}", surround.id()),
+            data_id: surround.id(),
+            kind: "surround",
+            parent: None,
+            label: &surround.data.name,
+            bbox: surround.box_rect().translated(surrounds_dx, 0.0).into(),
+            style_class: "surround",
+            fill_color,
+            text_color,
+            collapsed: false,
+            used_by: surround.used_by_set().into(),
+        });
+    }
+
+    // --- the connecting lines ---
+    for line in document.connecting_lines.iter() {
+        let (ctrl_1, ctrl_2) = line.control_points();
+        lines.push(JsonLine{
+            kind: "connection",
+            source: format!("surround_cap_{}", line.capability_id()),
+            target: format!("surround_{}", line.surround_id()),
+            start: shifted(line.start(), TREE_SHIFT),
+            control_points: [shifted(ctrl_1, TREE_SHIFT), shifted(ctrl_2, TREE_SHIFT)],
+            end: shifted(line.end(), TREE_SHIFT),
+            color: line.color(),
+            used_by: Some(line.used_by_set().into()),
+        });
+    }
+
+    // --- find the area it all covers ---
+    let bbox = BBox::from(Rect::new_cwh((0.0, 0.0), 2.0 * CENTER_DOT_RADIUS, 2.0 * CENTER_DOT_RADIUS))
+        .cover(&document.core_tree.get_bbox().translated(-TREE_SHIFT, 0.0))
+        .cover(&document.surround_tree.get_bbox().translated(TREE_SHIFT, 0.0))
+        .cover(&document.surrounds.get_bbox().translated(TREE_SHIFT, 0.0))
+        .rect()
+        .unwrap() // there is always a center dot
+        .into();
+
+    // --- write it out ---
+    serde_json::to_string_pretty(&JsonScene{bbox, nodes, lines})
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use super::*;
+    use crate::test_support::tiny_document;
+
+    fn scene_value(document: &TwoTreeViewDocument) -> Value {
+        serde_json::from_str(&scene_json(document).unwrap()).unwrap()
+    }
+
+    fn find<'a>(items: &'a Value, field: &str, value: &str) -> Option<&'a Value> {
+        items.as_array().unwrap().iter().find(|x| x[field] == value)
+    }
+
+    fn point_json(point: Point) -> Value {
+        json!([point.0, point.1])
+    }
+
+    #[test]
+    fn test_scene_json_fields() {
+        let mut document = tiny_document();
+        let scene = scene_value(&document);
+
+        // --- a capability keeps its id, label, place, and colors ---
+        let placed_bbox = document.core_tree.placed_nodes().iter()
+            .find(|placed| placed.data.id == "C1a")
+            .unwrap()
+            .bbox;
+        let (fill_color, text_color) = get_color_strs(&document.core_tree.find_data_by_id("C1a").unwrap().used_by_set);
+        assert_eq!(find(&scene["nodes"], "id", "core_cap_C1a"), Some(&json!({
+            "id": "core_cap_C1a",
+            "data_id": "C1a",
+            "kind": "capability",
+            "parent": "core_cap_C1",
+            "label": "Loans & <Notes>",
+            "bbox": JsonRect::from(placed_bbox.translated(-TREE_SHIFT, 0.0)),
+            "style_class": "leaf",
+            "fill_color": fill_color,
+            "text_color": text_color,
+            "collapsed": false,
+            "used_by": {"consumer": "Yes", "sbb": "No", "commercial": "No"},
+        })));
+        assert_eq!(find(&scene["nodes"], "id", "surround_SR2").unwrap()["used_by"]["sbb"], "Yes");
+
+        // --- a connecting line has the points of its curve, moved with the surround tree ---
+        let line = document.connecting_lines.iter().find(|x| x.capability_id() == "C1b").unwrap();
+        let (ctrl_1, ctrl_2) = line.control_points();
+        let json_line = find(&scene["lines"], "target", "surround_SR1").unwrap();
+        assert_eq!(json_line["kind"], "connection");
+        assert_eq!(json_line["source"], "surround_cap_C1b");
+        assert_eq!(json_line["start"], point_json(shifted(line.start(), TREE_SHIFT)));
+        assert_eq!(json_line["control_points"], json!([
+            point_json(shifted(ctrl_1, TREE_SHIFT)),
+            point_json(shifted(ctrl_2, TREE_SHIFT)),
+        ]));
+        assert_eq!(json_line["end"], point_json(shifted(line.end(), TREE_SHIFT)));
+        assert_eq!(json_line["color"], line.color());
+        assert_eq!(json_line["used_by"], json!({"consumer": "Yes", "sbb": "No", "commercial": "No"}));
+
+        // --- a tree line goes from the parent to the child ---
+        let tree_line = find(&scene["lines"], "target", "core_cap_C1a").unwrap();
+        assert_eq!(tree_line["source"], "core_cap_C1");
+        assert_eq!(tree_line["used_by"], Value::Null);
+
+        // --- a collapsed capability says so, and its children are left out ---
+        document.toggle_collapse("C1");
+        let scene = scene_value(&document);
+        assert_eq!(find(&scene["nodes"], "id", "core_cap_C1").unwrap()["collapsed"], true);
+        assert_eq!(find(&scene["nodes"], "id", "core_cap_C1a"), None);
+    }
+}
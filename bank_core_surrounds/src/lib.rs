@@ -1,5 +1,4 @@
 
-mod trifoil;
 mod used_by;
 mod capability_tree;
 mod capability_db;
@@ -9,6 +8,14 @@ mod capability_html;
 mod surrounds;
 mod connecting_lines;
 mod spaced_layout;
+mod crossing_order;
+mod text_export;
+mod drawio_export;
+mod json_export;
+mod diagnostics;
+mod theme;
+#[cfg(test)]
+mod test_support;
 
 
 
@@ -25,6 +32,8 @@ extern {
     pub fn log(s: &str);
     pub fn get_text_width(s: &str, font: &str) -> f32;
     pub fn get_text_height(s: &str, font: &str) -> f32;
+    #[wasm_bindgen(js_namespace = Date, js_name = now)]
+    fn date_now() -> f64;
 }
 
 /// The document we are displaying (and modifying on each update call) exists as a global variable.
@@ -60,15 +69,19 @@ pub fn initialize() {
 
 #[wasm_bindgen]
 pub fn get_style() -> String {
-    capability_html::style().into()
+    capability_html::style()
 }
 
 #[wasm_bindgen]
 pub fn get_svg() -> String {
-    match GLOBAL_DOCUMENT.lock().unwrap().get_svg_str() {
+    let mut doc = GLOBAL_DOCUMENT.lock().unwrap();
+    doc.set_generated_at(date_now());
+    let svg_str = match doc.get_svg_str() {
         Ok(s) => s,
         Err(_) => "<h1>Error</h1>".into(),
-    }
+    };
+    doc.finish_transitions();
+    svg_str
 }
 
 #[wasm_bindgen]
@@ -89,6 +102,84 @@ pub fn refold(named_fold: String) -> String {
     get_svg()
 }
 
+/// Switches to one of the built-in themes by name ("LIGHT", "DARK", or "PRINT"). Call
+/// get_style() again afterward, since the overlay colors change too.
+#[wasm_bindgen]
+pub fn set_theme(theme_name: String) -> String {
+    GLOBAL_DOCUMENT.lock().unwrap().set_theme(&theme_name);
+    get_svg()
+}
+
+/// Turns on (or off) drawing overlapping boxes and crossing lines on top of the document.
+#[wasm_bindgen]
+pub fn show_diagnostics(show: bool) -> String {
+    GLOBAL_DOCUMENT.lock().unwrap().set_show_diagnostics(show);
+    get_svg()
+}
+
+/// Returns a listing of the overlapping boxes and crossing lines in the document.
+#[wasm_bindgen]
+pub fn get_diagnostics_report() -> String {
+    diagnostics::report(&GLOBAL_DOCUMENT.lock().unwrap())
+}
+
+/// Returns the SVG scaled to fit on a named page size (eg: "A3" or "TABLOID") for printing.
+#[wasm_bindgen]
+pub fn get_print_svg(page_name: String) -> String {
+    let mut doc = GLOBAL_DOCUMENT.lock().unwrap();
+    doc.set_generated_at(date_now());
+    match doc.get_print_svg_str(&page_name) {
+        Ok(s) => s,
+        Err(_) => "<h1>Error</h1>".into(),
+    }
+}
+
+/// Returns the SVG unscaled, centered on a canvas of a named page size (eg: "A3").
+#[wasm_bindgen]
+pub fn get_canvas_svg(page_name: String) -> String {
+    let mut doc = GLOBAL_DOCUMENT.lock().unwrap();
+    doc.set_generated_at(date_now());
+    match doc.get_canvas_svg_str(&page_name) {
+        Ok(s) => s,
+        Err(_) => "<h1>Error</h1>".into(),
+    }
+}
+
+/// Returns the document as text in one of the known, named formats (eg: "MERMAID_FLOWCHART").
+#[wasm_bindgen]
+pub fn get_text_export(format_name: String) -> String {
+    text_export::export_text(&GLOBAL_DOCUMENT.lock().unwrap(), &format_name)
+}
+
+/// Returns the document as the contents of a draw.io (mxGraph XML) file.
+#[wasm_bindgen]
+pub fn get_drawio_xml() -> String {
+    match drawio_export::drawio_xml(&GLOBAL_DOCUMENT.lock().unwrap()) {
+        Ok(s) => s,
+        Err(_) => "<h1>Error</h1>".into(),
+    }
+}
+
+/// Returns the laid-out document as a JSON scene graph.
+#[wasm_bindgen]
+pub fn get_scene_json() -> String {
+    match json_export::scene_json(&GLOBAL_DOCUMENT.lock().unwrap()) {
+        Ok(s) => s,
+        Err(_) => "{\"error\": \"Export failed.\"}".into(),
+    }
+}
+
+/// Returns the capability or surround at (x, y), given in the coordinates of the SVG's
+/// viewBox, as JSON like {"kind":"capability","id":"BC1"}. Returns "null" if there is
+/// nothing there.
+#[wasm_bindgen]
+pub fn node_at(x: f64, y: f64) -> String {
+    match GLOBAL_DOCUMENT.lock().unwrap().node_at((x, y)) {
+        Some(element) => serde_json::json!({"kind": element.kind, "id": element.id}).to_string(),
+        None => "null".into(),
+    }
+}
+
 
 pub fn get_initial_document() -> TwoTreeViewDocument {
     // --- read the data ---
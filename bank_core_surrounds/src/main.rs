@@ -1,4 +1,3 @@
-mod trifoil;
 mod used_by;
 mod capability_tree;
 mod capability_db;
@@ -8,6 +7,14 @@ mod capability_html;
 mod surrounds;
 mod connecting_lines;
 mod spaced_layout;
+mod crossing_order;
+mod text_export;
+mod drawio_export;
+mod json_export;
+mod diagnostics;
+mod theme;
+#[cfg(test)]
+mod test_support;
 
 
 use calamine::Error;
@@ -34,6 +41,38 @@ fn main() -> Result<(), Error> {
     document.toggle_collapse("BC2");
     let _ = document.get_svg_str();
 
+    // --- optionally pick a theme (eg: "THEME_DARK") ---
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(pos) = args.iter().position(|x| x.starts_with("THEME_")) {
+        let theme_arg = args.remove(pos);
+        document.set_theme(&theme_arg["THEME_".len()..]);
+    }
+
+    // --- stamp SVGs with the time they were made ---
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).expect("Clock is before 1970.");
+    document.set_generated_at(now.as_millis() as f64);
+
+    // --- an exported SVG is drawn once, so it shouldn't animate the changes made above ---
+    document.finish_transitions();
+
+    // --- optionally print an export (eg: "SVG", "MERMAID_FLOWCHART", "DRAWIO", "JSON", "PRINT_A3", "CANVAS_A3", or "DIAGNOSTICS") ---
+    if let Some(format_name) = args.first() {
+        let exported = match format_name.as_str() {
+            "SVG" => document.get_svg_str().expect("SVG failed."),
+            name if name.starts_with("PRINT_") => document.get_print_svg_str(&name["PRINT_".len()..]).expect("Printable SVG failed."),
+            name if name.starts_with("CANVAS_") => document.get_canvas_svg_str(&name["CANVAS_".len()..]).expect("Canvas SVG failed."),
+            "DRAWIO" => drawio_export::drawio_xml(&document).expect("Export to draw.io failed."),
+            "JSON" => json_export::scene_json(&document).expect("Export to JSON failed."),
+            "DIAGNOSTICS" => {
+                document.set_show_diagnostics(true);
+                let _ = document.get_svg_str();
+                diagnostics::report(&document)
+            },
+            _ => text_export::export_text(&document, format_name),
+        };
+        println!("{}", exported);
+    }
+
     // --- print it ---
     println!("END");
     Ok(())
@@ -2,13 +2,14 @@
 // Contains the code to render a list of surrounds.
 //
 
-use prog_draw::svg_render::SvgPositioned;
-use prog_draw::geometry::{Point, Coord, Rect};
+use prog_draw::svg_render::{Hyperlink, SvgPositioned};
+use prog_draw::geometry::{BBox, Point, Coord, Rect};
 use prog_draw::text_size::get_system_text_sizer;
 use prog_draw::svg_writer::{Attributes, Renderable, TagWriter, TagWriterError};
 use crate::capability_db::{CapabilitiesDB, SurroundRow};
 use crate::used_by::{get_color_strs, UsedBy, UsedBySet};
 use crate::document::{BASELINE_RISE, NODE_ITEM_ROUND_CORNER, TEXT_ITEM_PADDING, ITEM_SPACING};
+use crate::theme::current_theme;
 use crate::spaced_layout;
 use crate::spaced_layout::Spaceable;
 
@@ -31,7 +32,7 @@ pub struct SurroundItems {
 
 /// Returns the (width, height) of the text string.
 fn get_text_size(text: &str) -> Point {
-    match get_system_text_sizer().text_size(text, "Arial", 14.0) {
+    match get_system_text_sizer().text_size(text, current_theme().font_family, 14.0) {
         Err(_) => panic!("Sizing isn't working."),
         Ok((width,height)) => (width as Coord, height as Coord)
     }
@@ -69,6 +70,11 @@ impl SurroundItem {
         self.desired_y = Some(y_loc);
     }
 
+    /// Returns the UsedBySet that decides the color of this surround.
+    pub fn used_by_set(&self) -> &UsedBySet {
+        &self.used_by_set
+    }
+
     /// Returns the actual y coordinate
     pub fn get_actual_y(&self) -> Option<Coord> {
         self.actual_y
@@ -113,6 +119,13 @@ impl SurroundItems {
         return None
     }
 
+    /// Measures the names again (needed after the font changes).
+    pub fn remeasure_text(&mut self) {
+        for item in self.items.iter_mut() {
+            item.text_size = get_text_size(&item.data.name);
+        }
+    }
+
     pub fn get_by_id(&self, id: &str) -> Option<&SurroundItem> {
         for item in self.items.iter() {
             if item.data.id.as_str() == id {
@@ -122,6 +135,11 @@ impl SurroundItems {
         return None
     }
 
+    /// Returns an iterator over all the SurroundItems.
+    pub fn iter(&self) -> impl Iterator<Item=&SurroundItem> {
+        self.items.iter()
+    }
+
     pub fn get_by_id_mut(&mut self, id: &str) -> Option<&mut SurroundItem> {
         for item in self.items.iter_mut() {
             if item.data.id.as_str() == id {
@@ -132,6 +150,11 @@ impl SurroundItems {
     }
 
 
+    /// Returns the x position that all of the items are shifted by.
+    pub fn x_position(&self) -> Coord {
+        self.x_position
+    }
+
     /// Calling this clears away all information about where individual items are placed.
     /// After doing so (and before rendering) calls will be made to re-position things.
     /// This call provides the first piece of information: the new x_position.
@@ -188,44 +211,40 @@ impl Renderable for SurroundItem {
 
         // --- decide on decoration & color ---
         let (box_color, text_color) = get_color_strs(&self.used_by_set);
+        let theme = current_theme();
         let is_new = self.data.is_new_system;
 
         // --- draw it ---
         if is_new {
             const RING_DIST: Coord = 2.5;
-            tag_writer.single_tag("rect", Attributes::from([
+            tag_writer.single_tag("rect", theme.node_box_style(theme.hollow_fill).add_to(Attributes::from([
                 ("x", &*(box_left - RING_DIST).to_string()),
                 ("y", &*(box_top - RING_DIST).to_string()),
                 ("width", &*(box_width + 2.0 * RING_DIST).to_string()),
                 ("height", &*(box_height + 2.0 * RING_DIST).to_string()),
                 ("rx", &*(NODE_ITEM_ROUND_CORNER + RING_DIST).to_string()),
-                ("fill", "#FFFFFF"),
-                ("stroke", "black"),
-                ("stroke-width", &*1.to_string()),
-            ]))?;
+            ])))?;
         }
-        tag_writer.single_tag("rect", Attributes::from([
+        let mut box_attributes = Attributes::from([
             ("x", &*box_left.to_string()),
             ("y", &*box_top.to_string()),
             ("width", &*box_width.to_string()),
             ("height", &*box_height.to_string()),
             ("rx", &*NODE_ITEM_ROUND_CORNER.to_string()),
-            ("fill", box_color),
-            ("stroke", "black"),
-            ("stroke-width", &*1.to_string()),
-            ("onclick", &format!("show_overlay_data('surround','{}')", self.data.id)),
-            ("class", "surround")
-        ]))?;
+        ]);
+        // --- clicking a linked surround follows the link, so only the others show the overlay ---
+        if self.link().is_none() {
+            box_attributes = box_attributes.with_field("onclick", format!("show_overlay_data('surround','{}')", self.data.id));
+        }
+        tag_writer.single_tag("rect", theme.node_box_style(box_color).add_to(box_attributes.with_field("class", "surround")))?;
         tag_writer.tag_with_text(
             "text",
-            Attributes::from([
+            theme.node_text_style(text_color).add_text_to(Attributes::from([
                 ("x", &*text_left.to_string()),
                 ("y", &*text_baseline.to_string()),
-                ("font-family", "Arial"),
-                ("fill", text_color),
-                ("style", "font-style: normal; font-size: 12.4px; pointer-events: none"), // FIXME: size for 14 and set this to 12.4 seems to work. WHY?
+                ("style", "font-style: normal; pointer-events: none"),
                 ("class", "surround"),
-            ]),
+            ])),
             &self.data.name
         )?;
 
@@ -235,12 +254,19 @@ impl Renderable for SurroundItem {
 }
 
 
-impl SvgPositioned for SurroundItem {
-    // Gives the bounding box for the node including text AND the box around it. Calling
-    // this when the node hasn't been correctly positioned will result in a panic. Because
+impl SurroundItem {
+    /// Returns the URL this surround links to (the first http or https one, if the sheet
+    /// lists several), or None if it doesn't have one. Anything else in the sheet (like a
+    /// "javascript:" URL) is never linked to.
+    pub fn link(&self) -> Option<&str> {
+        self.data.links.split_whitespace().find(|link| is_web_link(link))
+    }
+
+    // Gives the rect for the node including text AND the box around it. Calling this
+    // when the node hasn't been correctly positioned will result in a panic. Because
     // the parent takes care of x-positioning, this box will always have its left edge
     // at zero.
-    fn get_bbox(&self) -> Rect {
+    pub fn box_rect(&self) -> Rect {
         let left = 0.0;
         let center = self.actual_y.expect("Must position items before getting bbox.");
         let (text_width, text_height) = self.text_size;
@@ -251,13 +277,28 @@ impl SvgPositioned for SurroundItem {
     }
 }
 
+/// Returns true if the link is an http or https URL.
+fn is_web_link(link: &str) -> bool {
+    let link = link.to_ascii_lowercase();
+    link.starts_with("http://") || link.starts_with("https://")
+}
+
+impl SvgPositioned for SurroundItem {
+    fn get_bbox(&self) -> BBox {
+        self.box_rect().into()
+    }
+}
+
 impl Renderable for SurroundItems {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
         tag_writer.begin_tag("g", Attributes::from([
             ("transform", format!("translate({}, 0.0)", self.x_position))
         ]))?;
         for item in self.items.iter() {
-            item.render(tag_writer)?;
+            match item.link() {
+                Some(link) => Hyperlink::new(item).with_href(link, Some("_blank")).render(tag_writer)?,
+                None => item.render(tag_writer)?,
+            }
         }
         tag_writer.end_tag("g")?;
         Ok(())
@@ -265,11 +306,39 @@ impl Renderable for SurroundItems {
 }
 
 impl SvgPositioned for SurroundItems {
-    fn get_bbox(&self) -> Rect {
+    fn get_bbox(&self) -> BBox {
         self.items.iter()
             .map(|x| x.get_bbox())
-            .reduce(|r1, r2| r1.cover(&r2))
-            .unwrap() // panic if there are NO items in the SurroundItems
+            .collect::<BBox>()
             .translated(self.x_position, 0.0)
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prog_draw::svg_writer::TagWriterImpl;
+    use crate::document::TwoTreeViewDocument;
+    use crate::test_support::{tiny_capdb, use_fixed_width_text};
+
+    #[test]
+    fn test_links() {
+        use_fixed_width_text();
+        let mut capdb = tiny_capdb();
+        capdb.surrounds[0].links = "javascript:alert(1) HTTPS://example.com/ledger?a&b".to_string();
+        capdb.surrounds[1].links = "javascript:alert(1)".to_string();
+        let document = TwoTreeViewDocument::new(capdb);
+        let mut output: Vec<u8> = Vec::new();
+        document.surrounds.render(&mut TagWriterImpl::new(&mut output)).unwrap();
+        let svg = String::from_utf8(output).unwrap();
+
+        // --- only the web link is used, and the surround with it opens just the link ---
+        assert_eq!(document.surrounds.get_by_id("SR1").unwrap().link(), Some("HTTPS://example.com/ledger?a&b"));
+        assert_eq!(document.surrounds.get_by_id("SR2").unwrap().link(), None);
+        assert!(svg.contains("<a href=\"HTTPS://example.com/ledger?a&amp;b\" target=\"_blank\">"));
+        assert!(!svg.contains("javascript"));
+        assert!(!svg.contains("show_overlay_data('surround','SR1')"));
+        assert!(svg.contains("show_overlay_data('surround','SR2')"));
+    }
+}
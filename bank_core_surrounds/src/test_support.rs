@@ -0,0 +1,110 @@
+//
+// Builds a tiny document for the tests, with text measured by a fixed-width sizer so
+// that no real fonts are needed.
+//
+
+use std::sync::Once;
+use prog_draw::text_size::{set_system_text_sizer, TextSizeError, TextSizer};
+use crate::capability_db::{CapabilitiesDB, CapabilitiesRow, SurroundList, SurroundRow, SurroundSheetRow};
+use crate::capability_tree::CoreOrSurround;
+use crate::document::TwoTreeViewDocument;
+use crate::used_by::UsedBy;
+
+
+/// Measures every character as half the font size wide and each line as one font size tall.
+struct FixedWidthTextSizer;
+
+impl TextSizer for FixedWidthTextSizer {
+    fn text_size(&self, text: &str, _font_family: &str, font_size: f32) -> Result<(f32, f32), TextSizeError> {
+        Ok((text.chars().count() as f32 * font_size / 2.0, font_size))
+    }
+}
+
+/// Makes the system text sizer the fixed-width one (which is safe to call from every test).
+pub fn use_fixed_width_text() {
+    static SET_SIZER: Once = Once::new();
+    SET_SIZER.call_once(|| unsafe { set_system_text_sizer(&FixedWidthTextSizer) });
+}
+
+
+fn capability(id: &str, parent_id: &str, name: &str, core_surround: CoreOrSurround, ssr_id: Option<&str>) -> CapabilitiesRow {
+    CapabilitiesRow{
+        id: id.to_string(),
+        parent_id: parent_id.to_string(),
+        name: name.to_string(),
+        level: 0,
+        description: String::new(),
+        core_surround,
+        notes: String::new(),
+        used_by_consumer: UsedBy::Yes,
+        used_by_sbb: UsedBy::No,
+        used_by_commercial: UsedBy::No,
+        ssr_id: ssr_id.map(|x| x.to_string()),
+    }
+}
+
+fn surround_sheet_row(id: &str, consumer: &[&str], sbb: &[&str]) -> SurroundSheetRow {
+    SurroundSheetRow{
+        id: id.to_string(),
+        functionality: String::new(),
+        description: String::new(),
+        notes: String::new(),
+        core_surround: Default::default(),
+        consumer_current: Default::default(),
+        sbb_current: Default::default(),
+        commercial_current: Default::default(),
+        consumer_destination: SurroundList::new(consumer),
+        sbb_destination: SurroundList::new(sbb),
+        commercial_destination: Default::default(),
+    }
+}
+
+fn surround(id: &str, name: &str, consumer: bool, sbb: bool) -> SurroundRow {
+    SurroundRow{
+        id: id.to_string(),
+        name: name.to_string(),
+        is_core: false,
+        is_new_system: false,
+        is_current: false,
+        is_destination: true,
+        consumer_current: false,
+        sbb_current: false,
+        commercial_current: false,
+        consumer_destination: consumer,
+        sbb_destination: sbb,
+        commercial_destination: false,
+        app_on_mainframe: String::new(),
+        links: String::new(),
+    }
+}
+
+/// Returns a database with this tree of capabilities:
+///
+///   C1 "Lending and Credit" (in both trees)
+///     C1a "Loans & <Notes>" (core only)
+///     C1b "Deposit Accounts" (in both trees, connected to the "General Ledger" surround)
+///   C2 "Payments and Transfers" (surround only, connected to the "Payment Gateway" surround)
+pub fn tiny_capdb() -> CapabilitiesDB {
+    CapabilitiesDB{
+        capabilities: vec![
+            capability("C1", "ROOT", "Lending and Credit", CoreOrSurround::Blank, None),
+            capability("C1a", "C1", "Loans & <Notes>", CoreOrSurround::Core, None),
+            capability("C1b", "C1", "Deposit Accounts", CoreOrSurround::Mixed, Some("S1")),
+            capability("C2", "ROOT", "Payments and Transfers", CoreOrSurround::Surround, Some("S2")),
+        ],
+        surround_sheet_rows: vec![
+            surround_sheet_row("S1", &["General Ledger"], &[]),
+            surround_sheet_row("S2", &[], &["Payment Gateway"]),
+        ],
+        surrounds: vec![
+            surround("SR1", "General Ledger", true, false),
+            surround("SR2", "Payment Gateway", false, true),
+        ],
+    }
+}
+
+/// Returns the laid-out document for tiny_capdb().
+pub fn tiny_document() -> TwoTreeViewDocument {
+    use_fixed_width_text();
+    TwoTreeViewDocument::new(tiny_capdb())
+}
@@ -0,0 +1,392 @@
+//
+// Exports the capability trees (and the surrounds connected to them) as text in the
+// Mermaid and PlantUML diagram languages, so tools that render those natively can show
+// the diagram without needing the WASM bundle.
+//
+
+use std::collections::HashMap;
+use prog_draw::data_tree::{DTNode, TreeLayoutDirection};
+use crate::capability_tree::{CapabilityData, CapabilityNodeTree};
+use crate::document::{TwoTreeViewDocument, CORE_TREE_TITLE, SURROUND_TREE_TITLE, SURROUNDS_TITLE};
+use crate::surrounds::SurroundItem;
+use crate::used_by::{get_color_strs, UsedBySet};
+
+
+const CENTER_TITLE: &str = "Capabilities";
+const DEFAULT_TEXT_COLOR: &str = "#000000";
+
+
+/// The different kinds of items that appear in an Outline.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum ItemKind {
+    Center,
+    TreeRoot,
+    Capability,
+    Surround,
+}
+
+/// One entry in an Outline.
+#[derive(Debug)]
+struct OutlineItem<'a> {
+    node_id: String, // safe to use as an identifier; shared by every mention of the same surround
+    parent: Option<usize>,
+    depth: usize,
+    side: TreeLayoutDirection,
+    kind: ItemKind,
+    label: &'a str,
+    colors: (&'static str, &'static str), // (box, text)
+    collapsed: bool,
+}
+
+/// A pre-order listing of everything that is visible in the document, with the surrounds
+/// each visible leaf of the surround tree connects to listed as its children. All of the
+/// text formats are written from this.
+struct Outline<'a> {
+    items: Vec<OutlineItem<'a>>,
+}
+
+
+/// Returns a version of the id that is safe to use as an identifier in either language.
+fn safe_id(prefix: &str, id: &str) -> String {
+    let cleaned: String = id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() {c} else {'_'})
+        .collect();
+    format!("{}_{}", prefix, cleaned)
+}
+
+/// Returns the label text on a single line.
+fn single_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+/// Returns the label escaped for use inside double quotes in Mermaid.
+fn mermaid_label(text: &str) -> String {
+    single_line(text).replace('"', "#quot;")
+}
+
+
+impl<'a> Outline<'a> {
+    fn new(document: &'a TwoTreeViewDocument) -> Self {
+        // --- find which surrounds each capability connects to ---
+        let mut surrounds_by_capability: HashMap<&'a str, Vec<&'a SurroundItem>> = HashMap::new();
+        for line in document.connecting_lines.iter() {
+            if let Some(surround) = document.get_surround(line.surround_id()) {
+                let surrounds = surrounds_by_capability.entry(line.capability_id()).or_default();
+                if !surrounds.iter().any(|x| x.id() == surround.id()) {
+                    surrounds.push(surround);
+                }
+            }
+        }
+
+        // --- build the outline (the lines only come from the surround tree, and some
+        //     capabilities are in both trees, so only that one gets the surrounds) ---
+        let mut outline = Outline::with_center();
+        outline.add_tree(&document.core_tree, "core_cap", CORE_TREE_TITLE, None);
+        outline.add_tree(&document.surround_tree, "surround_cap", SURROUND_TREE_TITLE, Some(&surrounds_by_capability));
+        outline
+    }
+
+    /// Returns an outline with just the center in it.
+    fn with_center() -> Self {
+        let center_colors = get_color_strs(&UsedBySet::all_mixed());
+        let center = OutlineItem{
+            node_id: "center".to_string(),
+            parent: None,
+            depth: 0,
+            side: TreeLayoutDirection::Right,
+            kind: ItemKind::Center,
+            label: CENTER_TITLE,
+            colors: center_colors,
+            collapsed: false,
+        };
+        Outline{items: vec![center]}
+    }
+
+    /// Adds one of the capability trees (as a child of the center). If surrounds_by_capability
+    /// is given, the surrounds each capability connects to are added as its children.
+    fn add_tree(
+        &mut self,
+        tree: &'a CapabilityNodeTree,
+        prefix: &str,
+        title: &'a str,
+        surrounds_by_capability: Option<&HashMap<&'a str, Vec<&'a SurroundItem>>>
+    ) {
+        let side = tree.layout_direction();
+        self.items.push(OutlineItem{
+            node_id: safe_id(prefix, &tree.tree.data.id),
+            parent: Some(0),
+            depth: 1,
+            side,
+            kind: ItemKind::TreeRoot,
+            label: title,
+            colors: get_color_strs(&tree.tree.data.used_by_set),
+            collapsed: tree.tree.collapsed,
+        });
+        let root_idx = self.items.len() - 1;
+        if !tree.tree.collapsed {
+            for child in tree.tree.children.iter() {
+                self.add_node(child, root_idx, prefix, side, surrounds_by_capability);
+            }
+        }
+    }
+
+    /// Recursive subroutine of add_tree(). Only descends into nodes that aren't collapsed.
+    fn add_node(
+        &mut self,
+        node: &'a DTNode<CapabilityData>,
+        parent_idx: usize,
+        prefix: &str,
+        side: TreeLayoutDirection,
+        surrounds_by_capability: Option<&HashMap<&'a str, Vec<&'a SurroundItem>>>
+    ) {
+        let depth = self.items[parent_idx].depth + 1;
+        self.items.push(OutlineItem{
+            node_id: safe_id(prefix, &node.data.id),
+            parent: Some(parent_idx),
+            depth,
+            side,
+            kind: ItemKind::Capability,
+            label: &node.data.text,
+            colors: get_color_strs(&node.data.used_by_set),
+            collapsed: node.collapsed,
+        });
+        let idx = self.items.len() - 1;
+        if !node.collapsed {
+            for child in node.children.iter() {
+                self.add_node(child, idx, prefix, side, surrounds_by_capability);
+            }
+        }
+        if let Some(surrounds) = surrounds_by_capability.and_then(|x| x.get(node.data.id.as_str())) {
+            for surround in surrounds {
+                self.items.push(OutlineItem{
+                    node_id: safe_id("surround", surround.id()),
+                    parent: Some(idx),
+                    depth: depth + 1,
+                    side,
+                    kind: ItemKind::Surround,
+                    label: &surround.data.name,
+                    colors: get_color_strs(surround.used_by_set()),
+                    collapsed: false,
+                });
+            }
+        }
+    }
+
+    /// Writes the Mermaid flowchart lines declaring the trees (but not the surrounds) and
+    /// the edges between them. Returns how many edges were written.
+    fn write_mermaid_trees(&self, out: &mut String) -> usize {
+        let mut link_count: usize = 0;
+        for item in self.items.iter() {
+            let label = mermaid_label(item.label);
+            match item.kind {
+                ItemKind::Center => out.push_str(&format!("    {}((\"{}\"))\n", item.node_id, label)),
+                ItemKind::TreeRoot | ItemKind::Capability => out.push_str(&format!("    {}[\"{}\"]\n", item.node_id, label)),
+                ItemKind::Surround => continue, // declared separately, in their own subgraph
+            }
+            let (box_color, text_color) = item.colors;
+            let dash = if item.collapsed {",stroke-dasharray: 5 5"} else {""};
+            out.push_str(&format!("    style {} fill:{},color:{}{}\n", item.node_id, box_color, text_color, dash));
+            if let Some(parent_idx) = item.parent {
+                let parent = &self.items[parent_idx];
+                // edges on the left side point inward so the layout puts the children to the left
+                match item.side {
+                    TreeLayoutDirection::Left => out.push_str(&format!("    {} --- {}\n", item.node_id, parent.node_id)),
+                    TreeLayoutDirection::Right => out.push_str(&format!("    {} --- {}\n", parent.node_id, item.node_id)),
+                }
+                link_count += 1;
+            }
+        }
+        link_count
+    }
+
+    /// Returns the Mermaid mindmap of the outline.
+    fn mermaid_mindmap(&self) -> String {
+        let mut out = String::from("mindmap\n");
+        for (idx, item) in self.items.iter().enumerate() {
+            let indent = "  ".repeat(item.depth + 1);
+            let label = mermaid_label(item.label);
+            let shape = match item.kind {
+                ItemKind::Center => format!("((\"{}\"))", label),
+                ItemKind::TreeRoot | ItemKind::Capability => format!("[\"{}\"]", label),
+                ItemKind::Surround => format!("{{{{\"{}\"}}}}", label),
+            };
+            out.push_str(&format!("{}n{}{}\n", indent, idx, shape));
+        }
+        out
+    }
+
+    /// Returns the PlantUML work breakdown structure of the outline.
+    fn plantuml_wbs(&self) -> String {
+        let mut out = String::from("@startwbs\n");
+        for item in self.items.iter() {
+            let stars = "*".repeat(item.depth + 1);
+            out.push_str(&format!("{}{} {}\n", stars, plantuml_color(item), plantuml_label(item)));
+        }
+        out.push_str("@endwbs\n");
+        out
+    }
+
+    /// Returns the PlantUML mindmap of the outline.
+    fn plantuml_mindmap(&self) -> String {
+        let mut out = String::from("@startmindmap\n");
+        for item in self.items.iter() {
+            let marker = match item.side {
+                TreeLayoutDirection::Left if item.kind != ItemKind::Center => "-",
+                _ => "+",
+            };
+            let markers = marker.repeat(item.depth + 1);
+            out.push_str(&format!("{}{} {}\n", markers, plantuml_color(item), plantuml_label(item)));
+        }
+        out.push_str("@endmindmap\n");
+        out
+    }
+}
+
+
+/// Returns a Mermaid flowchart. The core tree grows to the left and the surround tree to
+/// the right; surrounds are drawn once each and connected with dotted lines.
+pub fn mermaid_flowchart(document: &TwoTreeViewDocument) -> String {
+    let outline = Outline::new(document);
+    let mut out = String::from("flowchart LR\n");
+    let mut link_styles: Vec<String> = Vec::new();
+
+    // --- declare the nodes and the tree edges ---
+    let mut link_count = outline.write_mermaid_trees(&mut out);
+
+    // --- declare the surrounds ---
+    out.push_str(&format!("    subgraph surrounds [\"{}\"]\n", mermaid_label(SURROUNDS_TITLE)));
+    for surround in document.surrounds.iter() {
+        let node_id = safe_id("surround", surround.id());
+        let (box_color, text_color) = get_color_strs(surround.used_by_set());
+        out.push_str(&format!("        {}([\"{}\"])\n", node_id, mermaid_label(&surround.data.name)));
+        out.push_str(&format!("        style {} fill:{},color:{}\n", node_id, box_color, text_color));
+    }
+    out.push_str("    end\n");
+
+    // --- connect capabilities to surrounds ---
+    for line in document.connecting_lines.iter() {
+        let capability_node_id = safe_id("surround_cap", line.capability_id());
+        let surround_node_id = safe_id("surround", line.surround_id());
+        out.push_str(&format!("    {} -.-> {}\n", capability_node_id, surround_node_id));
+        let (line_color, _) = get_color_strs(line.used_by_set());
+        link_styles.push(format!("    linkStyle {} stroke:{},stroke-width:2px\n", link_count, line_color));
+        link_count += 1;
+    }
+    for link_style in link_styles {
+        out.push_str(&link_style);
+    }
+    out
+}
+
+
+/// Returns a Mermaid mindmap. Surrounds are shown (as hexagons) beneath each capability
+/// that connects to them.
+pub fn mermaid_mindmap(document: &TwoTreeViewDocument) -> String {
+    Outline::new(document).mermaid_mindmap()
+}
+
+
+/// Returns the PlantUML color prefix for an item, like "[#FFC77F]".
+fn plantuml_color(item: &OutlineItem) -> String {
+    format!("[{}]", item.colors.0)
+}
+
+/// Returns the PlantUML text for an item, colored if its text isn't the default color.
+fn plantuml_label(item: &OutlineItem) -> String {
+    let text = single_line(item.label);
+    let text_color = item.colors.1;
+    if text_color == DEFAULT_TEXT_COLOR {
+        text
+    } else {
+        format!("<color:{}>{}</color>", text_color, text)
+    }
+}
+
+
+/// Returns a PlantUML work breakdown structure.
+pub fn plantuml_wbs(document: &TwoTreeViewDocument) -> String {
+    Outline::new(document).plantuml_wbs()
+}
+
+
+/// Returns a PlantUML mindmap. The core tree is placed on the left and the surround tree
+/// on the right.
+pub fn plantuml_mindmap(document: &TwoTreeViewDocument) -> String {
+    Outline::new(document).plantuml_mindmap()
+}
+
+
+/// Returns the document in one of the known, named text formats. If a name is passed
+/// in that isn't known, this will panic.
+///
+/// NOTE: It uses a string instead of an enum because it was designed to interact
+///   with JavaScript.
+pub fn export_text(document: &TwoTreeViewDocument, format_name: &str) -> String {
+    match format_name {
+        "MERMAID_FLOWCHART" => mermaid_flowchart(document),
+        "MERMAID_MINDMAP" => mermaid_mindmap(document),
+        "PLANTUML_WBS" => plantuml_wbs(document),
+        "PLANTUML_MINDMAP" => plantuml_mindmap(document),
+        _ => panic!("The name '{}' is not a known text export format.", format_name)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability_tree::{CoreOrSurround, TreeCollapsePolicy};
+    use crate::test_support::tiny_document;
+
+    /// Returns a tree with a child "A" (which has a child "A1") of the root.
+    fn small_tree(layout_direction: TreeLayoutDirection) -> CapabilityNodeTree {
+        let mut tree = CapabilityNodeTree::new(layout_direction, TreeCollapsePolicy::Nothing);
+        let used_by_set = UsedBySet::all_mixed();
+        for (id, parent_id) in [("A", "ROOT"), ("A1", "A")] {
+            tree.add_node(CapabilityData::new_new(id, parent_id, id, used_by_set, "", CoreOrSurround::Blank, "", false));
+        }
+        tree
+    }
+
+    #[test]
+    fn test_safe_id_and_mermaid_label() {
+        assert_eq!(safe_id("core_cap", "BC-1.2"), "core_cap_BC_1_2");
+        assert_eq!(safe_id("surround", "Ok9"), "surround_Ok9");
+        assert_eq!(mermaid_label("Say \"hi\"\nthere"), "Say #quot;hi#quot; there");
+    }
+
+    #[test]
+    fn test_outline_indentation_and_edges() {
+        let core_tree = small_tree(TreeLayoutDirection::Left);
+        let surround_tree = small_tree(TreeLayoutDirection::Right);
+        let mut outline = Outline::with_center();
+        outline.add_tree(&core_tree, "core_cap", "Core", None);
+        outline.add_tree(&surround_tree, "surround_cap", "Surround", None);
+
+        let wbs = outline.plantuml_wbs();
+        let wbs_lines: Vec<&str> = wbs.lines()
+            .map(|line| line.split('[').next().unwrap())
+            .collect();
+        assert_eq!(wbs_lines, ["@startwbs", "*", "**", "***", "****", "**", "***", "****", "@endwbs"]);
+        let mindmap = outline.plantuml_mindmap();
+        assert!(mindmap.contains("\n---[") && mindmap.contains("\n+++["));
+
+        // --- edges on the left point inward, and edges on the right point outward ---
+        let mut flowchart = String::new();
+        assert_eq!(outline.write_mermaid_trees(&mut flowchart), 6);
+        assert!(flowchart.contains("    core_cap_A1 --- core_cap_A\n"));
+        assert!(flowchart.contains("    surround_cap_A --- surround_cap_A1\n"));
+    }
+
+    #[test]
+    fn test_surrounds_only_in_surround_tree() {
+        let document = tiny_document();
+        let outline = Outline::new(&document);
+        let surround_parents: Vec<&str> = outline.items.iter()
+            .filter(|item| item.kind == ItemKind::Surround)
+            .map(|item| outline.items[item.parent.unwrap()].node_id.as_str())
+            .collect();
+        // C1b is in both trees, but only the surround tree's copy connects to anything
+        assert_eq!(surround_parents, ["surround_cap_C1b", "surround_cap_C2"]);
+    }
+}
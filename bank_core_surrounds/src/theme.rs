@@ -0,0 +1,212 @@
+//
+// A Theme holds every color and font choice used to draw the document, so the whole look
+// can be switched at once (for instance to a dark mode). There are a few built-in themes,
+// chosen by name; the one in use is kept in a threadlocal variable.
+//
+
+use std::cell::Cell;
+use prog_draw::geometry::Coord;
+use prog_draw::style::Style;
+use crate::document::{NODE_FONT_SIZE, TITLE_FONT_SIZE};
+
+
+/// Colors for the pop-up that shows the details of an item.
+#[derive(Debug)]
+pub struct OverlayColors {
+    /// Covers the rest of the page while the pop-up is open.
+    pub shade: &'static str,
+    pub background: &'static str,
+    pub text: &'static str,
+    pub border: &'static str,
+}
+
+
+#[derive(Debug)]
+pub struct Theme {
+    /// The color drawn behind everything, or None to leave it transparent.
+    pub background: Option<&'static str>,
+    /// The (box, text) colors for items used by each combination of consumer, sbb, and
+    /// commercial. The index has 4 for consumer, 2 for sbb, and 1 for commercial.
+    pub used_by_colors: [(&'static str, &'static str); 8],
+    /// The (box, text) colors for items where anything is Mixed.
+    pub mixed_colors: (&'static str, &'static str),
+    /// The (box, text) colors for items where anything is Blank or Maybe.
+    pub undecided_colors: (&'static str, &'static str),
+    /// The color for outlines of boxes and dots, and for solid dots.
+    pub outline: &'static str,
+    pub outline_width: Coord,
+    /// The color of the lines between parent and child capabilities.
+    pub tree_edge: &'static str,
+    /// The color inside hollow dots and rings.
+    pub hollow_fill: &'static str,
+    pub font_family: &'static str,
+    pub title_color: &'static str,
+    pub overlay: OverlayColors,
+}
+
+
+pub const LIGHT_THEME: Theme = Theme{
+    background: None,
+    used_by_colors: [
+        ("#FFFFFF", "#000000"),
+        ("#FF6163", "#000000"),
+        ("#8080FF", "#000000"),
+        ("#F58CFF", "#000000"),
+        ("#FFFF7F", "#000000"),
+        ("#FFC77F", "#000000"),
+        ("#80FF80", "#000000"),
+        ("#804000", "#FFFFFF"),
+    ],
+    mixed_colors: ("#E8E8E8", "#000000"),
+    undecided_colors: ("#E8E8E8", "#000000"),
+    outline: "#000000",
+    outline_width: 1.0,
+    tree_edge: "#000000",
+    hollow_fill: "#FFFFFF",
+    font_family: "Arial",
+    title_color: "#000000",
+    overlay: OverlayColors{
+        shade: "#00000080",
+        background: "#F7F7F7",
+        text: "#000000",
+        border: "#000000",
+    },
+};
+
+pub const DARK_THEME: Theme = Theme{
+    background: Some("#1E1E1E"),
+    used_by_colors: [
+        ("#2B2B2B", "#E0E0E0"),
+        ("#B23A3C", "#FFFFFF"),
+        ("#4A4AB8", "#FFFFFF"),
+        ("#A24CAD", "#FFFFFF"),
+        ("#B8B84A", "#000000"),
+        ("#C08A3E", "#000000"),
+        ("#3E9E3E", "#000000"),
+        ("#8A5A2B", "#FFFFFF"),
+    ],
+    mixed_colors: ("#3A3A3A", "#E0E0E0"),
+    undecided_colors: ("#3A3A3A", "#E0E0E0"),
+    outline: "#D0D0D0",
+    outline_width: 1.0,
+    tree_edge: "#A0A0A0",
+    hollow_fill: "#1E1E1E",
+    font_family: "Arial",
+    title_color: "#E0E0E0",
+    overlay: OverlayColors{
+        shade: "#000000B0",
+        background: "#2B2B2B",
+        text: "#E0E0E0",
+        border: "#A0A0A0",
+    },
+};
+
+/// Strong outlines and colors that stay distinct when printed.
+pub const PRINT_THEME: Theme = Theme{
+    background: Some("#FFFFFF"),
+    used_by_colors: [
+        ("#FFFFFF", "#000000"),
+        ("#FB8072", "#000000"),
+        ("#80B1D3", "#000000"),
+        ("#BC80BD", "#000000"),
+        ("#FFED6F", "#000000"),
+        ("#FDB462", "#000000"),
+        ("#B3DE69", "#000000"),
+        ("#A65628", "#FFFFFF"),
+    ],
+    mixed_colors: ("#D9D9D9", "#000000"),
+    undecided_colors: ("#D9D9D9", "#000000"),
+    outline: "#000000",
+    outline_width: 1.5,
+    tree_edge: "#000000",
+    hollow_fill: "#FFFFFF",
+    font_family: "Arial",
+    title_color: "#000000",
+    overlay: OverlayColors{
+        shade: "#00000080",
+        background: "#FFFFFF",
+        text: "#000000",
+        border: "#000000",
+    },
+};
+
+
+thread_local!{
+    /// This threadlocal variable holds the theme everything is drawn with.
+    static CURRENT_THEME: Cell<&'static Theme> = const { Cell::new(&LIGHT_THEME) };
+}
+
+/// Returns the theme everything is currently drawn with.
+pub fn current_theme() -> &'static Theme {
+    CURRENT_THEME.with(|it| it.get())
+}
+
+/// Sets the theme everything is drawn with. Most callers want
+/// TwoTreeViewDocument::set_theme() instead, which also redoes the layout.
+pub fn set_current_theme(theme: &'static Theme) {
+    CURRENT_THEME.with(|it| it.set(theme));
+}
+
+
+impl Theme {
+    /// Returns one of the built-in themes by its name ("LIGHT", "DARK", or "PRINT"), or
+    /// None if the name isn't known.
+    pub fn by_name(name: &str) -> Option<&'static Theme> {
+        match name {
+            "LIGHT" => Some(&LIGHT_THEME),
+            "DARK" => Some(&DARK_THEME),
+            "PRINT" => Some(&PRINT_THEME),
+            _ => None,
+        }
+    }
+
+    /// Returns the style for the box around a capability or surround.
+    pub fn node_box_style(&self, box_color: &str) -> Style {
+        Style::filled(box_color).with_stroke(self.outline, self.outline_width)
+    }
+
+    /// Returns the style for the name of a capability or surround.
+    pub fn node_text_style(&self, text_color: &str) -> Style {
+        Style::new()
+            .with_font_family(self.font_family)
+            .with_font_size(NODE_FONT_SIZE)
+            .with_text_color(text_color)
+    }
+
+    /// Returns the style for a small dot: solid, or hollow with an outline.
+    pub fn dot_style(&self, solid: bool) -> Style {
+        let fill = if solid {self.outline} else {self.hollow_fill};
+        Style::filled(fill).with_stroke(self.outline, 1.0)
+    }
+
+    /// Returns the style for the lines between parent and child capabilities.
+    pub fn tree_edge_style(&self) -> Style {
+        Style::new().with_stroke(self.tree_edge, self.outline_width)
+    }
+
+    /// Returns the style for the titles over each part of the document.
+    pub fn title_style(&self) -> Style {
+        Style::new()
+            .with_font_family(self.font_family)
+            .with_font_size(TITLE_FONT_SIZE)
+            .with_text_color(self.title_color)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::used_by::{get_color_strs, UsedBy, UsedBySet};
+
+    #[test]
+    fn test_used_by_color_index() {
+        let consumer_only = UsedBySet::from_fields(UsedBy::Yes, UsedBy::No, UsedBy::No);
+        let commercial_only = UsedBySet::from_fields(UsedBy::No, UsedBy::No, UsedBy::Yes);
+        assert_eq!(get_color_strs(&consumer_only), ("#FFFF7F", "#000000"));
+        assert_eq!(get_color_strs(&commercial_only), ("#FF6163", "#000000"));
+        set_current_theme(&DARK_THEME);
+        assert_eq!(get_color_strs(&UsedBySet::all_mixed()), DARK_THEME.mixed_colors);
+        set_current_theme(&LIGHT_THEME);
+    }
+}
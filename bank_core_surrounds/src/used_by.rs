@@ -1,5 +1,7 @@
 use core::convert::From;
 use core::default::Default;
+use prog_draw::legend::Legend;
+use crate::theme::current_theme;
 
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -72,32 +74,47 @@ impl UsedBySet {
     }
 }
 
-/// Returns the colors to use for (box, text) to represent this UsedBySet.
+/// Returns the colors to use for (box, text) to represent this UsedBySet in the current
+/// theme.
 pub fn get_color_strs(used_by_set: &UsedBySet) ->  (&'static str, &'static str) {
+    let theme = current_theme();
     let used_bys = [used_by_set.consumer, used_by_set.sbb, used_by_set.commercial];
     let has_mixed = used_bys.iter().any(|x| *x == UsedBy::Mixed);
     let has_undecided = used_bys.iter().any(|x| *x == UsedBy::Blank || *x == UsedBy::Maybe);
     if has_mixed {
-        ("#E8E8E8", "#000000") // grey box if anything is mixed
+        theme.mixed_colors
     } else if has_undecided {
-        ("#E8E8E8", "#000000") // grey box if anything is undecided
+        theme.undecided_colors
     } else { // colored box if everything is Yes or No
-        let bools = used_bys.map(|x| match x {
-            UsedBy::Yes => true,
-            UsedBy::No => false,
+        let index = used_bys.iter().fold(0, |index, x| match x {
+            UsedBy::Yes => 2 * index + 1,
+            UsedBy::No => 2 * index,
             _ => panic!()
         });
-        match bools {
-            [ true,  true,  true] => ("#804000", "#FFFFFF"),
-            [ true,  true, false] => ("#80FF80", "#000000"),
-            [ true, false,  true] => ("#FFC77F", "#000000"),
-            [ true, false, false] => ("#FFFF7F", "#000000"),
-            [false,  true,  true] => ("#F58CFF", "#000000"),
-            [false,  true, false] => ("#8080FF", "#000000"),
-            [false, false,  true] => ("#FF6163", "#000000"),
-            [false, false, false] => ("#FFFFFF", "#000000"),
-        }
+        theme.used_by_colors[index]
+    }
+}
+
+
+/// Returns the key explaining the colors that get_color_strs() gives in the current theme.
+pub fn used_by_legend() -> Legend {
+    let theme = current_theme();
+    let text_style = theme.node_text_style(theme.title_color);
+    let yes_or_no = |x: bool| if x {UsedBy::Yes} else {UsedBy::No};
+    let mut legend = Legend::from_sets(text_style, &["Consumer", "SBB", "Commercial"], |members| {
+        let used_by_set = UsedBySet::from_fields(yes_or_no(members[0]), yes_or_no(members[1]), yes_or_no(members[2]));
+        theme.node_box_style(get_color_strs(&used_by_set).0)
+    });
+    let mixed_color = get_color_strs(&UsedBySet::all_mixed()).0;
+    let undecided_color = get_color_strs(&UsedBySet::all_blank()).0;
+    if mixed_color == undecided_color {
+        legend.add_entry(theme.node_box_style(mixed_color), "Mixed or Undecided");
+    } else {
+        legend.add_entry(theme.node_box_style(mixed_color), "Mixed");
+        legend.add_entry(theme.node_box_style(undecided_color), "Undecided");
     }
+    legend.set_title(Some("Used By"));
+    legend
 }
 
 
@@ -4,12 +4,17 @@
 //
 
 
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
-use std::cell::Cell;
-use crate::svg_writer::{Renderable, TagWriter, Attributes, TagWriterError};
-use crate::svg_render::SvgPositioned;
-use crate::geometry::{Coord, Rect};
+use std::cell::{Cell, RefCell};
+use std::iter::Enumerate;
+use std::slice::{Iter, IterMut};
+use crate::svg_writer::{Renderable, TagWriter, TagWriterError};
+use crate::svg_render::{PathShape, SvgPositioned};
+use crate::marker::Marker;
+use crate::style::Style;
+use crate::geometry::{BBox, Coord, Path, Point, Rect};
 
 
 static LINE_CTRL_OFFSET: Coord = 10.0;
@@ -28,19 +33,59 @@ pub struct DTNode<T> {
 /// The AddData() item is used to add a data item as a child of the current node. StartChildren
 /// will make the most recently added item the new "current node". EndChildren will make the
 /// "current node" be the parent of the current "current node".
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum DTNodeBuild<T> {
     AddData(T),
     StartChildren(bool), // the bool indicates whether the children are collapsed
     EndChildren,
 }
 
-#[derive(Debug)]
-pub struct InvalidGrowth;
+/// The error from DTNode::grow_tree() when the items don't describe a tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidGrowth {
+    /// The position of the item with the problem among the items passed in.
+    pub index: usize,
+    pub kind: InvalidGrowthKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InvalidGrowthKind {
+    /// A StartChildren came before anything was added that could have the children.
+    StartChildrenWithoutNode,
+    /// An EndChildren came without a StartChildren for it to end.
+    UnmatchedEndChildren,
+}
+
 impl Error for InvalidGrowth {}
 impl Display for InvalidGrowth {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Invalid Growth")
+        let problem = match self.kind {
+            InvalidGrowthKind::StartChildrenWithoutNode => "StartChildren with no node to give children to",
+            InvalidGrowthKind::UnmatchedEndChildren => "EndChildren with no StartChildren to end",
+        };
+        write!(f, "Invalid Growth at item {}: {}", self.index, problem)
+    }
+}
+
+/// The ways an edit to the structure of a tree can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidEdit {
+    /// There is no node at this path.
+    NoSuchNode(Vec<usize>),
+    /// A node can't be moved to somewhere inside itself (including the root, which
+    /// everything is inside).
+    MoveIntoItself,
+    /// The index is past the end of the children it would be inserted into.
+    IndexOutOfRange(usize),
+}
+impl Error for InvalidEdit {}
+impl Display for InvalidEdit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidEdit::NoSuchNode(path) => write!(f, "Invalid Edit: no node at {:?}", path),
+            InvalidEdit::MoveIntoItself => write!(f, "Invalid Edit: cannot move a node inside itself"),
+            InvalidEdit::IndexOutOfRange(index) => write!(f, "Invalid Edit: no place for a child at {}", index),
+        }
     }
 }
 
@@ -56,6 +101,14 @@ thread_local!{
     /// This threadlocal variable is set before rendering to say which way the tree
     /// should be laid out. If NOT set, it defaults to laying out to the right.
     pub static LAYOUT_DIRECTION: Cell<Option<TreeLayoutDirection>> = Cell::new(None);
+
+    /// This threadlocal variable is the style used for the lines between parent and child
+    /// nodes when rendering. It defaults to thin black lines.
+    pub static EDGE_STYLE: RefCell<Style> = RefCell::new(Style::new());
+
+    /// This threadlocal variable holds the (start, end) markers drawn on the lines between
+    /// parent and child nodes when rendering. It defaults to no markers.
+    pub static EDGE_MARKERS: RefCell<(Option<Marker>, Option<Marker>)> = const { RefCell::new((None, None)) };
 }
 
 
@@ -113,34 +166,41 @@ impl<T> DTNode<T> {
     }
 
 
-    /// Initialize a tree with a syntax that allows us to pass an iterator.
+    /// Initialize a tree with a syntax that allows us to pass an iterator. If the items
+    /// don't describe a tree, this returns an error saying which item is wrong and leaves
+    /// the tree unchanged. (Children that are started but never ended are fine.)
     ///
     /// Example:
     /// ```
-    /// let mut root = DTNode::new(MyNode::new("ROOT", &mut id_source));
+    /// use prog_draw::data_tree::{DTNode, DTNodeBuild::{AddData, EndChildren, StartChildren}};
+    ///
+    /// let mut root = DTNode::new("ROOT");
     /// root.grow_tree([
-    ///     AddData(MyNode::new(core_0, &mut id_source)),
-    ///     StartChildren,
-    ///     AddData(MyNode::new(core_0_0, &mut id_source)),
-    ///     StartChildren,
-    ///     AddData(MyNode::new(core_0_0_0, &mut id_source)),
-    ///     AddData(MyNode::new(core_0_0_1, &mut id_source)),
-    ///     AddData(MyNode::new(core_0_0_2, &mut id_source)),
+    ///     AddData("core_0"),
+    ///     StartChildren(false),
+    ///     AddData("core_0_0"),
+    ///     StartChildren(true),
+    ///     AddData("core_0_0_0"),
+    ///     AddData("core_0_0_1"),
+    ///     AddData("core_0_0_2"),
     ///     EndChildren,
-    ///     AddData(MyNode::new(core_0_1, &mut id_source)),
-    /// ]);
+    ///     AddData("core_0_1"),
+    /// ]).unwrap();
+    /// assert_eq!(root.len(), 7);
     /// ```
     pub fn grow_tree(&mut self, items: impl IntoIterator<Item=DTNodeBuild<T>>) -> Result<(),InvalidGrowth> {
+        let items: Vec<DTNodeBuild<T>> = items.into_iter().collect();
+        self.check_growth(&items)?;
+
         let mut stack: Vec<usize> = vec![];
         let mut current = &mut *self;
-
         for action in items {
             match action {
                 DTNodeBuild::AddData(data) => {
                     current.add_child_data(data);
                 },
                 DTNodeBuild::StartChildren(collapsed) => {
-                    stack.push(current.children.len() - 1); // FIXME: watch for underflow
+                    stack.push(current.children.len() - 1); // check_growth() made sure there is one
                     current = current.children.last_mut().unwrap().as_mut();
                     current.collapsed = collapsed
                 }
@@ -157,6 +217,60 @@ impl<T> DTNode<T> {
         Ok(())
     }
 
+    /// Returns the first problem that would come up growing this tree with the items.
+    fn check_growth(&self, items: &[DTNodeBuild<T>]) -> Result<(), InvalidGrowth> {
+        // --- for each level of children started: the node (if it was already in the
+        //     tree, and None if it's being added) and how many children have been added ---
+        let mut levels: Vec<(Option<&DTNode<T>>, usize)> = vec![(Some(self), 0)];
+        for (index, item) in items.iter().enumerate() {
+            let (existing, added) = *levels.last().unwrap();
+            match item {
+                DTNodeBuild::AddData(_) => {
+                    levels.last_mut().unwrap().1 += 1;
+                },
+                DTNodeBuild::StartChildren(_) => {
+                    let last_child = match (existing, added) {
+                        (_, 1..) => Some(None), // the last child was just added
+                        (Some(node), 0) => node.children.last().map(|child| Some(child.as_ref())),
+                        (None, 0) => None,
+                    };
+                    match last_child {
+                        Some(node) => levels.push((node, 0)),
+                        None => return Err(InvalidGrowth{index, kind: InvalidGrowthKind::StartChildrenWithoutNode}),
+                    }
+                },
+                DTNodeBuild::EndChildren => {
+                    if levels.len() == 1 {
+                        return Err(InvalidGrowth{index, kind: InvalidGrowthKind::UnmatchedEndChildren});
+                    }
+                    levels.pop();
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the items that grow_tree() would need to give a new node with this node's
+    /// data the same descendants as this one. (Only this node's own collapsed flag is lost.)
+    pub fn flatten(&self) -> Vec<DTNodeBuild<T>> where T: Clone {
+        /// Internal recursive subroutine.
+        fn add_children<T: Clone>(items: &mut Vec<DTNodeBuild<T>>, node: &DTNode<T>) {
+            for child in node.children.iter() {
+                items.push(DTNodeBuild::AddData(child.data.clone()));
+                // a collapsed node with no children still needs a StartChildren to say so
+                if !child.children.is_empty() || child.collapsed {
+                    items.push(DTNodeBuild::StartChildren(child.collapsed));
+                    add_children(items, child);
+                    items.push(DTNodeBuild::EndChildren);
+                }
+            }
+        }
+
+        let mut items = Vec::new();
+        add_children(&mut items, self);
+        items
+    }
+
 
     #[allow(dead_code)] // FIXME: Remove this if it remains unused after a long time
     /// Add a node (which may have its own children) as a child of this node.
@@ -164,65 +278,509 @@ impl<T> DTNode<T> {
         self.children.push(Box::new(child));
     }
 
+    /// Returns the children that are shown: all of them, unless visible_only is set and
+    /// this node is collapsed.
+    fn shown_children(&self, visible_only: bool) -> &[Box<DTNode<T>>] {
+        match visible_only && self.collapsed {
+            true => &self.children[..0],
+            false => &self.children,
+        }
+    }
+
+    /// Returns an iterator over this node and all its descendants in the given order. If
+    /// visible_only is set, it doesn't go inside collapsed nodes (but does visit them).
+    pub fn traverse(&self, order: TraversalOrder, visible_only: bool) -> Traversal<'_, T> {
+        let root = Visit{node: self, parent: None, depth: 0, path: Vec::new()};
+        let mut traversal = Traversal{order, visible_only, pending: VecDeque::new(), stack: Vec::new()};
+        match order {
+            TraversalOrder::PostOrder => {
+                let children = root.node.shown_children(visible_only).iter().enumerate();
+                traversal.stack.push((root, children));
+            },
+            _ => traversal.pending.push_back(root),
+        }
+        traversal
+    }
+
+    /// Like traverse(), but allows the data (and collapsed flag) of each node to be changed.
+    /// Whether a node is collapsed is checked when it is reached, so with visible_only set
+    /// in PreOrder or BreadthFirst order, changing it won't change which nodes get visited.
+    pub fn traverse_mut(&mut self, order: TraversalOrder, visible_only: bool) -> TraversalMut<'_, T> {
+        let mut traversal = TraversalMut{order, visible_only, pending: VecDeque::new(), stack: Vec::new()};
+        match order {
+            TraversalOrder::PostOrder => traversal.stack.push(VisitMut::split(self, 0, Vec::new(), visible_only)),
+            _ => traversal.pending.push_back((self, 0, Vec::new())),
+        }
+        traversal
+    }
+
+    /// Returns an iterator over this node and all its descendants, each before its children.
+    pub fn iter(&self) -> Traversal<'_, T> {
+        self.traverse(TraversalOrder::PreOrder, false)
+    }
+
+    /// Like iter(), but allows the data (and collapsed flag) of each node to be changed.
+    pub fn iter_mut(&mut self) -> TraversalMut<'_, T> {
+        self.traverse_mut(TraversalOrder::PreOrder, false)
+    }
+
+    /// Returns the node at the path (the index of the child to take at each step down from
+    /// this node), or None if there isn't one. The empty path is this node.
+    pub fn get(&self, path: &[usize]) -> Option<&DTNode<T>> {
+        path.iter().try_fold(self, |node, index| node.children.get(*index).map(|x| x.as_ref()))
+    }
+
+    /// Like get(), but the node can be changed.
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut DTNode<T>> {
+        path.iter().try_fold(self, |node, index| node.children.get_mut(*index).map(|x| x.as_mut()))
+    }
+
+    /// Returns the path to the first node (in pre-order) whose data matches the predicate.
+    pub fn find_path(&self, predicate: impl Fn(&T) -> bool) -> Option<Vec<usize>> {
+        self.iter().find(|visit| predicate(&visit.node.data)).map(|visit| visit.path)
+    }
+
+    /// Returns the first node (in pre-order) whose data matches the predicate.
+    pub fn find(&self, predicate: impl Fn(&T) -> bool) -> Option<&DTNode<T>> {
+        self.iter().find(|visit| predicate(&visit.node.data)).map(|visit| visit.node)
+    }
+
+    /// Like find(), but the node can be changed.
+    pub fn find_mut(&mut self, predicate: impl Fn(&T) -> bool) -> Option<&mut DTNode<T>> {
+        let path = self.find_path(predicate)?;
+        self.get_mut(&path)
+    }
+
+    /// Adds a node (which may have its own children) as a child of this node, at the index
+    /// given among the existing children. It panics if the index is more than the number
+    /// of children.
+    pub fn insert_child_node(&mut self, index: usize, child: DTNode<T>) {
+        self.children.insert(index, Box::new(child));
+    }
+
+    /// Removes the child at the index (along with all its descendants) and returns it. It
+    /// panics if there is no child at that index.
+    pub fn remove_child_node(&mut self, index: usize) -> DTNode<T> {
+        *self.children.remove(index)
+    }
+
+    /// Removes the node at the path (along with all its descendants) and returns it. This
+    /// node itself can't be detached (so the empty path returns an error).
+    pub fn detach(&mut self, path: &[usize]) -> Result<DTNode<T>, InvalidEdit> {
+        let no_such_node = || InvalidEdit::NoSuchNode(path.to_vec());
+        let (index, parent_path) = path.split_last().ok_or_else(no_such_node)?;
+        match self.get_mut(parent_path) {
+            Some(parent) if *index < parent.children.len() => Ok(parent.remove_child_node(*index)),
+            _ => Err(no_such_node()),
+        }
+    }
+
+    /// Moves the node at the path (along with all its descendants) to be the child at the
+    /// index of the node at new_parent_path. Both paths are from before the move, but the
+    /// index is counted after the node is removed, so when it stays under the same parent
+    /// the index is still where it ends up. Returns the path the node ends up at.
+    pub fn move_subtree(&mut self, path: &[usize], new_parent_path: &[usize], index: usize) -> Result<Vec<usize>, InvalidEdit> {
+        // --- check everything first, so nothing changes if it fails ---
+        if new_parent_path.starts_with(path) {
+            return Err(InvalidEdit::MoveIntoItself);
+        }
+        if self.get(path).is_none() {
+            return Err(InvalidEdit::NoSuchNode(path.to_vec()));
+        }
+        let new_parent = self.get(new_parent_path).ok_or_else(|| InvalidEdit::NoSuchNode(new_parent_path.to_vec()))?;
+        let (old_index, old_parent_path) = path.split_last().unwrap(); // the root is inside itself, so not empty
+        let sibling_count = match old_parent_path == new_parent_path {
+            true => new_parent.children.len() - 1, // it won't count itself once it's removed
+            false => new_parent.children.len(),
+        };
+        if index > sibling_count {
+            return Err(InvalidEdit::IndexOutOfRange(index));
+        }
+
+        // --- removing it shifts its later siblings (and anything in them) back by one ---
+        let mut destination = new_parent_path.to_vec();
+        let depth = old_parent_path.len();
+        if destination.len() > depth && destination.starts_with(old_parent_path) && destination[depth] > *old_index {
+            destination[depth] -= 1;
+        }
+
+        // --- move it ---
+        let subtree = self.detach(path)?;
+        self.get_mut(&destination).unwrap().insert_child_node(index, subtree);
+        destination.push(index);
+        Ok(destination)
+    }
+
+    /// Puts the children of this node in order by the key found from each one's data.
+    /// Children with equal keys stay in the order they were in.
+    pub fn sort_children_by_key<K: Ord>(&mut self, mut key: impl FnMut(&T) -> K) {
+        self.children.sort_by_key(|child| key(&child.data));
+    }
+
+    /// Like sort_children_by_key(), but sorts the children of every node in the tree.
+    pub fn sort_tree_by_key<K: Ord>(&mut self, mut key: impl FnMut(&T) -> K) {
+        /// Internal recursive subroutine.
+        fn sort_node<T, K: Ord>(node: &mut DTNode<T>, key: &mut impl FnMut(&T) -> K) {
+            node.sort_children_by_key(&mut *key);
+            for child in node.children.iter_mut() {
+                sort_node(child, key);
+            }
+        }
+        sort_node(self, &mut key);
+    }
 }
 
-impl<T: SvgPositioned> Renderable for DTNode<T> {
-    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+
+/// The orders in which a traversal can visit the nodes of a tree.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TraversalOrder {
+    /// Each node comes before its children.
+    PreOrder,
+    /// Each node comes after its children.
+    PostOrder,
+    /// Every node at one depth comes before any at the next depth.
+    BreadthFirst,
+}
+
+/// A node reached by traverse(), along with where it is in the tree.
+#[derive(Debug)]
+pub struct Visit<'a, T> {
+    pub node: &'a DTNode<T>,
+    pub parent: Option<&'a DTNode<T>>,
+    /// How far below the node the traversal started from (which has depth 0).
+    pub depth: usize,
+    /// The index of the child taken at each step down from the starting node.
+    pub path: Vec<usize>,
+}
+
+impl<'a, T> Visit<'a, T> {
+    fn child(&self, index: usize, child: &'a DTNode<T>) -> Self {
+        let mut path = self.path.clone();
+        path.push(index);
+        Visit{node: child, parent: Some(self.node), depth: self.depth + 1, path}
+    }
+}
+
+/// A node reached by traverse_mut(). Its children are visited on their own, so this only
+/// has the parts of the node that aren't part of the tree structure.
+#[derive(Debug)]
+pub struct VisitMut<'a, T> {
+    pub data: &'a mut T,
+    pub collapsed: &'a mut bool,
+    /// How far below the node the traversal started from (which has depth 0).
+    pub depth: usize,
+    /// The index of the child taken at each step down from the starting node.
+    pub path: Vec<usize>,
+}
+
+impl<'a, T> VisitMut<'a, T> {
+    /// Splits a node into the visit for it and its children that are still to be visited.
+    fn split(
+        node: &'a mut DTNode<T>,
+        depth: usize,
+        path: Vec<usize>,
+        visible_only: bool
+    ) -> FrameMut<'a, T> {
+        let DTNode{data, collapsed, children} = node;
+        let shown = if visible_only && *collapsed {0} else {children.len()};
+        (VisitMut{data, collapsed, depth, path}, children[..shown].iter_mut().enumerate())
+    }
+}
+
+/// A node being visited along with its children that are still to be visited.
+type Frame<'a, T> = (Visit<'a, T>, Enumerate<Iter<'a, Box<DTNode<T>>>>);
+type FrameMut<'a, T> = (VisitMut<'a, T>, Enumerate<IterMut<'a, Box<DTNode<T>>>>);
+
+/// The iterator returned by DTNode::traverse().
+pub struct Traversal<'a, T> {
+    order: TraversalOrder,
+    visible_only: bool,
+    /// The nodes still to be visited (for PreOrder and BreadthFirst).
+    pending: VecDeque<Visit<'a, T>>,
+    /// The nodes whose children are being visited, deepest last (for PostOrder).
+    stack: Vec<Frame<'a, T>>,
+}
+
+impl<'a, T> Iterator for Traversal<'a, T> {
+    type Item = Visit<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.order {
+            TraversalOrder::PostOrder => loop {
+                let (visit, children) = self.stack.last_mut()?;
+                match children.next() {
+                    Some((index, child)) => {
+                        let child_visit = visit.child(index, child);
+                        let grandchildren = child.shown_children(self.visible_only).iter().enumerate();
+                        self.stack.push((child_visit, grandchildren));
+                    },
+                    None => return self.stack.pop().map(|(visit, _)| visit),
+                }
+            },
+            TraversalOrder::PreOrder => {
+                let visit = self.pending.pop_back()?;
+                for (index, child) in visit.node.shown_children(self.visible_only).iter().enumerate().rev() {
+                    self.pending.push_back(visit.child(index, child));
+                }
+                Some(visit)
+            },
+            TraversalOrder::BreadthFirst => {
+                let visit = self.pending.pop_front()?;
+                for (index, child) in visit.node.shown_children(self.visible_only).iter().enumerate() {
+                    self.pending.push_back(visit.child(index, child));
+                }
+                Some(visit)
+            },
+        }
+    }
+}
+
+/// The iterator returned by DTNode::traverse_mut().
+pub struct TraversalMut<'a, T> {
+    order: TraversalOrder,
+    visible_only: bool,
+    /// The nodes still to be visited, with their depth and path (for PreOrder and BreadthFirst).
+    pending: VecDeque<(&'a mut DTNode<T>, usize, Vec<usize>)>,
+    /// The nodes whose children are being visited, deepest last (for PostOrder).
+    stack: Vec<FrameMut<'a, T>>,
+}
+
+impl<'a, T> Iterator for TraversalMut<'a, T> {
+    type Item = VisitMut<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth, path) = match self.order {
+            TraversalOrder::PostOrder => loop {
+                let (visit, children) = self.stack.last_mut()?;
+                match children.next() {
+                    Some((index, child)) => {
+                        let mut child_path = visit.path.clone();
+                        child_path.push(index);
+                        let child_split = VisitMut::split(child, visit.depth + 1, child_path, self.visible_only);
+                        self.stack.push(child_split);
+                    },
+                    None => return self.stack.pop().map(|(visit, _)| visit),
+                }
+            },
+            TraversalOrder::PreOrder => self.pending.pop_back()?,
+            TraversalOrder::BreadthFirst => self.pending.pop_front()?,
+        };
+        let (visit, children) = VisitMut::split(node, depth, path, self.visible_only);
+        let children = children.map(|(index, child)| {
+            let mut child_path = visit.path.clone();
+            child_path.push(index);
+            (child.as_mut(), depth + 1, child_path)
+        });
+        match self.order {
+            TraversalOrder::PreOrder => {
+                let children: Vec<_> = children.collect();
+                self.pending.extend(children.into_iter().rev());
+            },
+            _ => self.pending.extend(children),
+        }
+        Some(visit)
+    }
+}
+
+/// Returns the points of the curve drawn from a parent node to one of its children: the end
+/// at the parent, the two control points, and the end at the child (in that order). The
+/// curve leaves from the side of the parent that faces the child.
+pub fn child_line_points(parent_bbox: &Rect, child_bbox: &Rect, direction: TreeLayoutDirection) -> [Point; 4] {
+    let leftward = direction == TreeLayoutDirection::Left;
+    let parent_line_end_x = if leftward {parent_bbox.left()} else {parent_bbox.right()};
+    let parent_line_end_y = parent_bbox.top() + parent_bbox.height() / 2.0;
+    let parent_line_ctrl_x = parent_line_end_x + LINE_CTRL_OFFSET * if leftward {-1.0} else {1.0};
+    let parent_line_ctrl_y = parent_line_end_y;
+    let child_line_end_x = if leftward {child_bbox.right()} else {child_bbox.left()};
+    let child_line_end_y = child_bbox.top() + child_bbox.height() / 2.0;
+    let child_line_ctrl_x = child_line_end_x + LINE_CTRL_OFFSET * if leftward {1.0} else {-1.0};
+    let child_line_ctrl_y = child_line_end_y;
+    [
+        (parent_line_end_x, parent_line_end_y),
+        (parent_line_ctrl_x, parent_line_ctrl_y),
+        (child_line_ctrl_x, child_line_ctrl_y),
+        (child_line_end_x, child_line_end_y),
+    ]
+}
+
+
+impl<T: SvgPositioned> DTNode<T> {
+    /// Draws just the lines between each visible parent and its children.
+    pub fn render_edges(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
         if !self.collapsed {
             // --- Use context to decide whether to draw to the right or the left ---
-            let leftward: bool = match LAYOUT_DIRECTION.with(|it| it.get()) {
-                Some(TreeLayoutDirection::Left) => true,
-                _ => false,
+            let direction = match LAYOUT_DIRECTION.with(|it| it.get()) {
+                Some(TreeLayoutDirection::Left) => TreeLayoutDirection::Left,
+                _ => TreeLayoutDirection::Right,
             };
 
             // --- Draw lines to child nodes ---
-            let parent_bbox = self.data.get_bbox();
-            let parent_line_end_x = if leftward {parent_bbox.left()} else {parent_bbox.right()};
-            let parent_line_end_y = parent_bbox.top() + parent_bbox.height() / 2.0;
-            let parent_line_ctrl_x = parent_line_end_x + LINE_CTRL_OFFSET * if leftward {-1.0} else {1.0};
-            let parent_line_ctrl_y = parent_line_end_y;
+            let parent_bbox = self.data.get_bbox().rect();
             for child in self.children.iter() {
-                let child_bbox = child.data.get_bbox();
-                let child_line_end_x = if leftward {child_bbox.right()} else {child_bbox.left()};
-                let child_line_end_y = child_bbox.top() + child_bbox.height() / 2.0;
-                let child_line_ctrl_x = child_line_end_x + LINE_CTRL_OFFSET * if leftward {1.0} else {-1.0};
-                let child_line_ctrl_y = child_line_end_y;
-                let path_code: String = format_args!(
-                    "M {} {} C {} {}, {} {}, {} {}",
-                    parent_line_end_x, parent_line_end_y,
-                    parent_line_ctrl_x, parent_line_ctrl_y,
-                    child_line_ctrl_x, child_line_ctrl_y,
-                    child_line_end_x, child_line_end_y
-                ).to_string();
-                tag_writer.single_tag("path", Attributes::from([
-                    ("d", &*path_code),
-                    ("fill", "none"),
-                    ("stroke", "black"),
-                ]))?;
+                // things that take up no space have nothing to connect a line to
+                let (parent_bbox, child_bbox) = match (parent_bbox, child.data.get_bbox().rect()) {
+                    (Some(parent_bbox), Some(child_bbox)) => (parent_bbox, child_bbox),
+                    _ => continue,
+                };
+                let [parent_end, parent_ctrl, child_ctrl, child_end] = child_line_points(&parent_bbox, &child_bbox, direction);
+                let path = Path::new(parent_end).cubic_to(parent_ctrl, child_ctrl, child_end);
+                let (start_marker, end_marker) = EDGE_MARKERS.with(|it| it.borrow().clone());
+                EDGE_STYLE.with(|it| PathShape::new(path, it.borrow().clone()))
+                    .with_markers(start_marker, end_marker)
+                    .render(tag_writer)?;
             }
 
-            // --- Draw child nodes ---
+            // --- Draw lines within the children ---
             for child in self.children.iter() {
-                child.render(tag_writer)?;
+                child.render_edges(tag_writer)?;
             }
         }
-
-        // --- Draw this node ---
-        self.data.render(tag_writer)?;
         Ok(())
     }
+
+    /// Draws just the visible nodes (with children before their parents).
+    pub fn render_nodes(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        if !self.collapsed {
+            for child in self.children.iter() {
+                child.render_nodes(tag_writer)?;
+            }
+        }
+        self.data.render(tag_writer)
+    }
+}
+
+impl<T: SvgPositioned> Renderable for DTNode<T> {
+    /// Draws every line under every node.
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        self.render_edges(tag_writer)?;
+        self.render_nodes(tag_writer)
+    }
 }
 
 impl<T: SvgPositioned> SvgPositioned for DTNode<T> {
     // Returns the bbox that covers the root node AND all non-collapsed descendant nodes.
-    fn get_bbox(&self) -> Rect {
-        let root_rect: Rect = self.data.get_bbox();
+    fn get_bbox(&self) -> BBox {
+        let root_bbox: BBox = self.data.get_bbox();
         if self.collapsed {
-            root_rect
+            root_bbox
         } else {
             self.children.iter()
                 .map(|child| child.get_bbox())
-                .fold(root_rect, |r1, r2| r1.cover(&r2))
+                .fold(root_bbox, |b1, b2| b1.cover(&b2))
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DTNodeBuild::{AddData, EndChildren, StartChildren};
+
+    /// Returns the tree A(B(D, E), C(F)) with C collapsed.
+    fn sample_tree() -> DTNode<&'static str> {
+        let mut tree = DTNode::new("A");
+        tree.grow_tree([
+            AddData("B"),
+            StartChildren(false),
+            AddData("D"),
+            AddData("E"),
+            EndChildren,
+            AddData("C"),
+            StartChildren(true),
+            AddData("F"),
+            EndChildren,
+        ]).unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_traversal_orders() {
+        let tree = sample_tree();
+        let names = |order, visible_only| -> String {
+            tree.traverse(order, visible_only).map(|visit| visit.node.data).collect()
+        };
+        assert_eq!(names(TraversalOrder::PreOrder, false), "ABDECF");
+        assert_eq!(names(TraversalOrder::PostOrder, false), "DEBFCA");
+        assert_eq!(names(TraversalOrder::BreadthFirst, false), "ABCDEF");
+        assert_eq!(names(TraversalOrder::PreOrder, true), "ABDEC");
+        assert_eq!(names(TraversalOrder::PostOrder, true), "DEBCA");
+
+        let f = tree.iter().find(|visit| visit.node.data == "F").unwrap();
+        assert_eq!((f.depth, f.path, f.parent.map(|x| x.data)), (2, vec![1, 0], Some("C")));
+    }
+
+    #[test]
+    fn test_traversal_mut() {
+        let mut tree = sample_tree();
+        for order in [TraversalOrder::PreOrder, TraversalOrder::PostOrder, TraversalOrder::BreadthFirst] {
+            let mut visits: Vec<(&str, Vec<usize>)> = tree.traverse_mut(order, true)
+                .map(|visit| (*visit.data, visit.path))
+                .collect();
+            visits.sort();
+            assert_eq!(visits, [("A", vec![]), ("B", vec![0]), ("C", vec![1]), ("D", vec![0, 0]), ("E", vec![0, 1])]);
+        }
+
+        for visit in tree.iter_mut() {
+            *visit.collapsed = visit.depth == 1;
+        }
+        let visible: String = tree.traverse(TraversalOrder::PreOrder, true).map(|visit| visit.node.data).collect();
+        assert_eq!(visible, "ABC");
+    }
+
+    #[test]
+    fn test_structural_edits() {
+        let mut tree = sample_tree();
+        let names = |tree: &DTNode<&str>| -> String { tree.iter().map(|visit| visit.node.data).collect() };
+        assert_eq!(tree.find_path(|x| *x == "E"), Some(vec![0, 1]));
+        assert_eq!(tree.get(&[1, 0]).map(|x| x.data), Some("F"));
+        assert!(tree.get(&[2]).is_none());
+
+        // --- move E to be the first child of C, past where its old sibling was ---
+        assert_eq!(tree.move_subtree(&[0, 1], &[1], 0), Ok(vec![1, 0]));
+        assert_eq!(names(&tree), "ABDCEF");
+        // --- move E after F; the index counts the siblings left once E is removed ---
+        assert_eq!(tree.move_subtree(&[1, 0], &[1], 1), Ok(vec![1, 1]));
+        assert_eq!(names(&tree), "ABDCFE");
+        // --- move G into C, whose path shifts from [2] to [1] when G (before it) is removed ---
+        tree.insert_child_node(0, DTNode::new("G"));
+        assert_eq!(tree.move_subtree(&[0], &[2], 0), Ok(vec![1, 0]));
+        assert_eq!(names(&tree), "ABDCGFE");
+        // --- move C under D, which is before it, so nothing shifts ---
+        assert_eq!(tree.move_subtree(&[1], &[0, 0], 0), Ok(vec![0, 0, 0]));
+        assert_eq!(names(&tree), "ABDCGFE");
+        assert_eq!(tree.move_subtree(&[0], &[0, 0, 0], 0), Err(InvalidEdit::MoveIntoItself));
+        assert_eq!(tree.move_subtree(&[0, 0], &[], 2), Err(InvalidEdit::IndexOutOfRange(2)));
+        assert_eq!(tree.detach(&[]).err(), Some(InvalidEdit::NoSuchNode(vec![])));
+
+        let c = tree.detach(&[0, 0, 0]).unwrap();
+        assert_eq!((c.data, c.len()), ("C", 4));
+        tree.add_child_node(c);
+        tree.find_mut(|x| *x == "C").unwrap().add_child_data("A");
+        tree.sort_tree_by_key(|x| *x);
+        assert_eq!(names(&tree), "ABDCAEFG");
+    }
+
+    #[test]
+    fn test_grow_tree_errors_and_flatten() {
+        let mut tree = DTNode::new("A");
+        let error = tree.grow_tree([StartChildren(false)]).unwrap_err();
+        assert_eq!(error, InvalidGrowth{index: 0, kind: InvalidGrowthKind::StartChildrenWithoutNode});
+        let error = tree.grow_tree([AddData("B"), StartChildren(false), EndChildren, EndChildren]).unwrap_err();
+        assert_eq!(error, InvalidGrowth{index: 3, kind: InvalidGrowthKind::UnmatchedEndChildren});
+        let error = tree.grow_tree([AddData("B"), StartChildren(false), StartChildren(false)]).unwrap_err();
+        assert_eq!(error, InvalidGrowth{index: 2, kind: InvalidGrowthKind::StartChildrenWithoutNode});
+        assert_eq!(tree.len(), 1); // nothing was added by the failures
+
+        let tree = sample_tree();
+        let items = tree.flatten();
+        assert_eq!(items[..3], [AddData("B"), StartChildren(false), AddData("D")]);
+        let mut copy = DTNode::new(tree.data);
+        copy.grow_tree(items.clone()).unwrap();
+        assert_eq!(copy.flatten(), items);
+
+        // --- growing more onto existing children continues from the last one ---
+        copy.grow_tree([StartChildren(false), AddData("G"), StartChildren(true), EndChildren]).unwrap();
+        assert_eq!(copy.get(&[1, 1]).map(|x| (x.data, x.collapsed)), Some(("G", true)));
+    }
+}
@@ -0,0 +1,221 @@
+//
+// Checks a finished scene for things that make it hard to read: boxes that sit on top of
+// each other and lines that cross. The results are plain data (so tests can check them)
+// and can also be drawn on top of the scene to show where the problems are.
+//
+
+use crate::geometry::{segment_intersection, BBox, Coord, Path, Point, Rect};
+use crate::svg_render::SvgPositioned;
+use crate::svg_writer::{Attributes, Renderable, TagWriter, TagWriterError};
+
+
+/// Number of straight pieces each curve is split into when looking for crossings.
+const CROSSING_STEPS: usize = 32;
+/// Crossings closer together than this are counted as the same crossing.
+const SAME_POINT_DISTANCE: Coord = 1e-6;
+/// Radius of the circle drawn around each crossing in the overlay.
+const CROSSING_MARK_RADIUS: Coord = 4.0;
+const OVERLAY_COLOR: &str = "#FF0000";
+
+
+/// A box in the scene that shouldn't overlap any other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneBox {
+    pub id: String,
+    pub kind: &'static str,
+    pub rect: Rect,
+}
+
+/// A line in the scene that it's best not to cross.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneLine {
+    pub id: String,
+    pub path: Path,
+}
+
+/// Two boxes that overlap, and the area they share.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlap {
+    pub first: SceneBox,
+    pub second: SceneBox,
+    pub area: Rect,
+}
+
+/// A place where two lines cross.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crossing {
+    pub first_id: String,
+    pub second_id: String,
+    pub point: Point,
+}
+
+/// Everything found when checking a scene.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diagnostics {
+    pub overlaps: Vec<Overlap>,
+    pub crossings: Vec<Crossing>,
+}
+
+
+/// Returns every pair of boxes that share some area. (Boxes that just touch along an edge
+/// don't count.)
+pub fn find_overlaps(boxes: &[SceneBox]) -> Vec<Overlap> {
+    let mut answer: Vec<Overlap> = Vec::new();
+    for (i, first) in boxes.iter().enumerate() {
+        for second in boxes[i + 1..].iter() {
+            if first.rect.overlaps(&second.rect) {
+                if let BBox::Area(area) = first.rect.intersection(&second.rect) {
+                    answer.push(Overlap{first: first.clone(), second: second.clone(), area});
+                }
+            }
+        }
+    }
+    answer
+}
+
+
+/// Returns true if the point is (very nearly) the same as another.
+fn same_point(p1: Point, p2: Point) -> bool {
+    (p1.0 - p2.0).hypot(p1.1 - p2.1) < SAME_POINT_DISTANCE
+}
+
+
+/// Returns the places where two lines cross. Lines meeting at a shared end (like two
+/// lines leaving the same box from the same spot) aren't counted as crossing there.
+fn crossings_between(first: &SceneLine, second: &SceneLine) -> Vec<Crossing> {
+    let shared_ends: Vec<Point> = [first.path.start(), first.path.end()].into_iter()
+        .filter(|p| same_point(*p, second.path.start()) || same_point(*p, second.path.end()))
+        .collect();
+    let first_points = first.path.flattened(CROSSING_STEPS);
+    let second_points = second.path.flattened(CROSSING_STEPS);
+    let mut points: Vec<Point> = Vec::new();
+    for a in first_points.windows(2) {
+        for b in second_points.windows(2) {
+            if let Some(point) = segment_intersection(a[0], a[1], b[0], b[1]) {
+                let already_found = points.iter().any(|p| same_point(*p, point));
+                let at_shared_end = shared_ends.iter().any(|p| same_point(*p, point));
+                if !already_found && !at_shared_end {
+                    points.push(point);
+                }
+            }
+        }
+    }
+    points.into_iter()
+        .map(|point| Crossing{first_id: first.id.clone(), second_id: second.id.clone(), point})
+        .collect()
+}
+
+
+/// Returns every place where two of the lines cross.
+pub fn find_crossings(lines: &[SceneLine]) -> Vec<Crossing> {
+    let mut answer: Vec<Crossing> = Vec::new();
+    for (i, first) in lines.iter().enumerate() {
+        for second in lines[i + 1..].iter() {
+            // lines whose boxes don't touch can't cross, and that's much cheaper to check
+            if first.path.bbox().intersection(&second.path.bbox()).is_empty() {
+                continue;
+            }
+            answer.extend(crossings_between(first, second));
+        }
+    }
+    answer
+}
+
+
+impl Diagnostics {
+    /// Checks the boxes for overlaps and the lines for crossings.
+    pub fn new(boxes: &[SceneBox], lines: &[SceneLine]) -> Self {
+        Diagnostics{overlaps: find_overlaps(boxes), crossings: find_crossings(lines)}
+    }
+
+    /// Returns true if nothing was found.
+    pub fn is_clean(&self) -> bool {
+        self.overlaps.is_empty() && self.crossings.is_empty()
+    }
+}
+
+
+impl Renderable for Diagnostics {
+    /// Draws the overlapping areas and circles around the crossings.
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        tag_writer.begin_tag("g", Attributes::from([
+            ("class", "diagnostics"),
+            ("pointer-events", "none"),
+        ]))?;
+        for overlap in self.overlaps.iter() {
+            tag_writer.single_tag("rect", Attributes::from([
+                ("x", overlap.area.left().to_string()),
+                ("y", overlap.area.top().to_string()),
+                ("width", overlap.area.width().to_string()),
+                ("height", overlap.area.height().to_string()),
+                ("fill", OVERLAY_COLOR.to_string()),
+                ("fill-opacity", "0.4".to_string()),
+                ("stroke", OVERLAY_COLOR.to_string()),
+            ]))?;
+        }
+        for crossing in self.crossings.iter() {
+            tag_writer.single_tag("circle", Attributes::from([
+                ("cx", crossing.point.0.to_string()),
+                ("cy", crossing.point.1.to_string()),
+                ("r", CROSSING_MARK_RADIUS.to_string()),
+                ("fill", "none".to_string()),
+                ("stroke", OVERLAY_COLOR.to_string()),
+                ("stroke-width", "2".to_string()),
+            ]))?;
+        }
+        tag_writer.end_tag("g")?;
+        Ok(())
+    }
+}
+
+impl SvgPositioned for Diagnostics {
+    fn get_bbox(&self) -> BBox {
+        let mark_size = 2.0 * CROSSING_MARK_RADIUS;
+        self.overlaps.iter()
+            .map(|overlap| BBox::from(overlap.area))
+            .chain(self.crossings.iter().map(|crossing| Rect::new_cwh(crossing.point, mark_size, mark_size).into()))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene_box(id: &str, rect: Rect) -> SceneBox {
+        SceneBox{id: id.to_string(), kind: "box", rect}
+    }
+
+    fn scene_line(id: &str, path: Path) -> SceneLine {
+        SceneLine{id: id.to_string(), path}
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let boxes = [
+            scene_box("a", Rect::new_ltwh(0.0, 0.0, 10.0, 10.0)),
+            scene_box("b", Rect::new_ltwh(5.0, 5.0, 10.0, 10.0)),
+            scene_box("c", Rect::new_ltwh(10.0, 0.0, 10.0, 6.0)), // touches "a" along an edge
+        ];
+        let overlaps = find_overlaps(&boxes);
+        assert_eq!(overlaps.len(), 2);
+        assert_eq!((overlaps[0].first.id.as_str(), overlaps[0].second.id.as_str()), ("a", "b"));
+        assert_eq!(overlaps[0].area, Rect::new_ltwh(5.0, 5.0, 5.0, 5.0));
+        assert_eq!((overlaps[1].first.id.as_str(), overlaps[1].second.id.as_str()), ("b", "c"));
+    }
+
+    #[test]
+    fn test_crossings() {
+        let lines = [
+            scene_line("down", Path::new((0.0, 0.0)).cubic_to((10.0, 0.0), (20.0, 20.0), (30.0, 20.0))),
+            scene_line("up", Path::new((0.0, 20.0)).cubic_to((10.0, 20.0), (20.0, 0.0), (30.0, 0.0))),
+            scene_line("same_start", Path::new((0.0, 0.0)).line_to((30.0, -10.0))),
+        ];
+        let crossings = find_crossings(&lines);
+        assert_eq!(crossings.len(), 1);
+        assert_eq!((crossings[0].first_id.as_str(), crossings[0].second_id.as_str()), ("down", "up"));
+        let (x, y) = crossings[0].point;
+        assert!((x - 15.0).abs() < 1e-6 && (y - 10.0).abs() < 1e-6);
+    }
+}
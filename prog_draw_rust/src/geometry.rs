@@ -1,10 +1,11 @@
 
-// FIXME: Should I define my own? Pull from somewhere else? Should it be usize instead?
+// Coords are measured in CSS pixels (96 to the inch); see units for converting to
+// physical sizes.
 pub type Coord = f64;
 
 pub type Point = (Coord, Coord);
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Rect {
     left: Coord,
     top: Coord,
@@ -77,4 +78,944 @@ impl Rect {
         Rect::new_cwh(self.center(), s * self.width, s * self.height)
     }
 
+    /// Returns true if the point is inside this Rect (or on its edge).
+    pub fn contains_point(&self, p: Point) -> bool {
+        self.left() <= p.0 && p.0 <= self.right() && self.top() <= p.1 && p.1 <= self.bottom()
+    }
+
+    /// Returns true if all of other is inside this Rect (or on its edge).
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.left() <= other.left() && other.right() <= self.right() &&
+            self.top() <= other.top() && other.bottom() <= self.bottom()
+    }
+
+    /// Returns the area that is in both self and other. Rects that only touch along an
+    /// edge have an intersection with no area (but that isn't empty).
+    pub fn intersection(&self, other: &Rect) -> BBox {
+        let left = f64::max(self.left(), other.left());
+        let top = f64::max(self.top(), other.top());
+        let right = f64::min(self.right(), other.right());
+        let bottom = f64::min(self.bottom(), other.bottom());
+        if left > right || top > bottom {
+            BBox::Empty
+        } else {
+            BBox::Area(Rect::new_ltrb(left, top, right, bottom))
+        }
+    }
+
+    /// Returns true if self and other share any area. (Just touching along an edge
+    /// doesn't count.)
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.left() < other.right() && other.left() < self.right() &&
+            self.top() < other.bottom() && other.top() < self.bottom()
+    }
+
+    /// Returns a new Rect that is this one grown outward by the margins.
+    pub fn inflated(&self, margins: &Margins) -> Rect {
+        Rect::new_ltrb(
+            self.left() - margins.left,
+            self.top() - margins.top,
+            self.right() + margins.right,
+            self.bottom() + margins.bottom,
+        )
+    }
+
+    /// Returns this Rect shrunk inward by the margins. If the margins are bigger than
+    /// the Rect then nothing is left and it returns BBox::Empty.
+    pub fn deflated(&self, margins: &Margins) -> BBox {
+        let left = self.left() + margins.left;
+        let top = self.top() + margins.top;
+        let right = self.right() - margins.right;
+        let bottom = self.bottom() - margins.bottom;
+        if left > right || top > bottom {
+            BBox::Empty
+        } else {
+            BBox::Area(Rect::new_ltrb(left, top, right, bottom))
+        }
+    }
+}
+
+
+/// Space to leave around each side of something.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Margins {
+    pub left: Coord,
+    pub top: Coord,
+    pub right: Coord,
+    pub bottom: Coord,
+}
+
+impl Margins {
+    pub fn new(left: Coord, top: Coord, right: Coord, bottom: Coord) -> Self {
+        Margins{left, top, right, bottom}
+    }
+
+    /// Margins that are the same on every side.
+    pub fn uniform(m: Coord) -> Self {
+        Margins{left: m, top: m, right: m, bottom: m}
+    }
+}
+
+
+/// A bounding box, which (unlike a Rect) can be empty. Things that take up no space at
+/// all (like a group with nothing in it) have an empty BBox, and covering an empty BBox
+/// doesn't change anything.
+///
+/// A BBox can be built from a sequence of them with collect(), which gives the BBox that
+/// covers everything in the sequence.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum BBox {
+    #[default]
+    Empty,
+    Area(Rect),
+}
+
+impl BBox {
+    pub fn is_empty(&self) -> bool {
+        matches!(self, BBox::Empty)
+    }
+
+    /// Returns the Rect, or None if this is empty.
+    pub fn rect(&self) -> Option<Rect> {
+        match self {
+            BBox::Empty => None,
+            BBox::Area(rect) => Some(*rect),
+        }
+    }
+
+    /// Returns the smallest BBox that covers both self and other.
+    pub fn cover(&self, other: &BBox) -> BBox {
+        match (self, other) {
+            (BBox::Empty, _) => *other,
+            (_, BBox::Empty) => *self,
+            (BBox::Area(r1), BBox::Area(r2)) => BBox::Area(r1.cover(r2)),
+        }
+    }
+
+    /// Returns the area that is in both self and other.
+    pub fn intersection(&self, other: &BBox) -> BBox {
+        match (self, other) {
+            (BBox::Area(r1), BBox::Area(r2)) => r1.intersection(r2),
+            _ => BBox::Empty,
+        }
+    }
+
+    /// Returns true if the point is inside this BBox (or on its edge). Nothing is inside
+    /// an empty BBox.
+    pub fn contains_point(&self, p: Point) -> bool {
+        match self {
+            BBox::Empty => false,
+            BBox::Area(rect) => rect.contains_point(p),
+        }
+    }
+
+    /// Returns true if all of other is inside this BBox. An empty BBox is inside anything.
+    pub fn contains(&self, other: &BBox) -> bool {
+        match (self, other) {
+            (_, BBox::Empty) => true,
+            (BBox::Empty, _) => false,
+            (BBox::Area(r1), BBox::Area(r2)) => r1.contains(r2),
+        }
+    }
+
+    /// Returns a new BBox that is this one grown outward by the margins. Growing an empty
+    /// BBox leaves it empty.
+    pub fn inflated(&self, margins: &Margins) -> BBox {
+        match self {
+            BBox::Empty => BBox::Empty,
+            BBox::Area(rect) => BBox::Area(rect.inflated(margins)),
+        }
+    }
+
+    /// Returns a new BBox that is this one shrunk inward by the margins.
+    pub fn deflated(&self, margins: &Margins) -> BBox {
+        match self {
+            BBox::Empty => BBox::Empty,
+            BBox::Area(rect) => rect.deflated(margins),
+        }
+    }
+
+    /// Returns a new BBox that is the old one but translated by (dx,dy).
+    pub fn translated(&self, dx: Coord, dy: Coord) -> BBox {
+        match self {
+            BBox::Empty => BBox::Empty,
+            BBox::Area(rect) => BBox::Area(rect.translated(dx, dy)),
+        }
+    }
+}
+
+impl From<Rect> for BBox {
+    fn from(rect: Rect) -> Self {
+        BBox::Area(rect)
+    }
+}
+
+impl FromIterator<BBox> for BBox {
+    fn from_iter<I: IntoIterator<Item=BBox>>(iter: I) -> Self {
+        iter.into_iter().fold(BBox::Empty, |accum, bbox| accum.cover(&bbox))
+    }
+}
+
+
+/// A 2D affine transform. It is stored as the matrix
+///
+/// ```text
+/// | a c e |
+/// | b d f |
+/// | 0 0 1 |
+/// ```
+///
+/// which is the same form SVG uses for "matrix(a, b, c, d, e, f)". Angles are in degrees,
+/// as they are in SVG.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    a: Coord,
+    b: Coord,
+    c: Coord,
+    d: Coord,
+    e: Coord,
+    f: Coord,
+}
+
+impl Transform {
+    /// Construct a Transform from the six values of its matrix.
+    pub fn new_matrix(a: Coord, b: Coord, c: Coord, d: Coord, e: Coord, f: Coord) -> Self {
+        Transform{a, b, c, d, e, f}
+    }
+
+    /// The transform that leaves everything where it is.
+    pub fn identity() -> Self {
+        Transform::new_matrix(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    /// A transform that moves things by (dx,dy).
+    pub fn translate(dx: Coord, dy: Coord) -> Self {
+        Transform::new_matrix(1.0, 0.0, 0.0, 1.0, dx, dy)
+    }
+
+    /// A transform that scales by s about the origin.
+    pub fn scale(s: Coord) -> Self {
+        Transform::scale_xy(s, s)
+    }
+
+    /// A transform that scales by sx horizontally and sy vertically about the origin.
+    pub fn scale_xy(sx: Coord, sy: Coord) -> Self {
+        Transform::new_matrix(sx, 0.0, 0.0, sy, 0.0, 0.0)
+    }
+
+    /// A transform that scales by s, keeping the point center fixed.
+    pub fn scale_about(s: Coord, center: Point) -> Self {
+        Transform::translate(-center.0, -center.1)
+            .then(&Transform::scale(s))
+            .then(&Transform::translate(center.0, center.1))
+    }
+
+    /// A transform that rotates clockwise (on screen, where y points down) by the given
+    /// angle about the origin.
+    pub fn rotate(degrees: Coord) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Transform::new_matrix(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// A transform that rotates by the given angle about the point center.
+    pub fn rotate_about(degrees: Coord, center: Point) -> Self {
+        Transform::translate(-center.0, -center.1)
+            .then(&Transform::rotate(degrees))
+            .then(&Transform::translate(center.0, center.1))
+    }
+
+    /// A transform that skews along the x axis by the given angle.
+    pub fn skew_x(degrees: Coord) -> Self {
+        Transform::new_matrix(1.0, 0.0, degrees.to_radians().tan(), 1.0, 0.0, 0.0)
+    }
+
+    /// A transform that skews along the y axis by the given angle.
+    pub fn skew_y(degrees: Coord) -> Self {
+        Transform::new_matrix(1.0, degrees.to_radians().tan(), 0.0, 1.0, 0.0, 0.0)
+    }
+
+    /// Returns the transform that applies self first and then other.
+    pub fn then(&self, other: &Transform) -> Self {
+        other.compose(self)
+    }
+
+    /// Returns the transform that applies other first and then self. This is the order
+    /// SVG uses: "A B" means B is applied to the content first, then A.
+    pub fn compose(&self, other: &Transform) -> Self {
+        Transform{
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    /// Returns true if this transform leaves everything where it is.
+    pub fn is_identity(&self) -> bool {*self == Transform::identity()}
+
+    /// Returns where the point p ends up.
+    pub fn apply(&self, p: Point) -> Point {
+        (
+            self.a * p.0 + self.c * p.1 + self.e,
+            self.b * p.0 + self.d * p.1 + self.f,
+        )
+    }
+
+    /// Returns the smallest Rect that covers the rect once it has been transformed. (If
+    /// there is rotation or skew that will be bigger than the transformed rect itself.)
+    pub fn transform_rect(&self, rect: &Rect) -> Rect {
+        let corners = [
+            self.apply((rect.left(), rect.top())),
+            self.apply((rect.right(), rect.top())),
+            self.apply((rect.left(), rect.bottom())),
+            self.apply((rect.right(), rect.bottom())),
+        ];
+        let xs = corners.map(|p| p.0);
+        let ys = corners.map(|p| p.1);
+        Rect::new_ltrb(
+            xs.into_iter().fold(f64::INFINITY, f64::min),
+            ys.into_iter().fold(f64::INFINITY, f64::min),
+            xs.into_iter().fold(f64::NEG_INFINITY, f64::max),
+            ys.into_iter().fold(f64::NEG_INFINITY, f64::max),
+        )
+    }
+
+    /// Returns the smallest BBox that covers the bbox once it has been transformed.
+    pub fn transform_bbox(&self, bbox: &BBox) -> BBox {
+        match bbox {
+            BBox::Empty => BBox::Empty,
+            BBox::Area(rect) => BBox::Area(self.transform_rect(rect)),
+        }
+    }
+
+    /// Returns the transform that undoes this one, or None if it squashes things flat
+    /// (like a scale of 0) and so can't be undone.
+    pub fn inverse(&self) -> Option<Transform> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let a = self.d / det;
+        let b = -self.b / det;
+        let c = -self.c / det;
+        let d = self.a / det;
+        Some(Transform{
+            a, b, c, d,
+            e: -(a * self.e + c * self.f),
+            f: -(b * self.e + d * self.f),
+        })
+    }
+
+    /// Returns the value for an SVG "transform" attribute, or None if this is the identity
+    /// (so there's no need for the attribute). Simple translates and scales are written
+    /// the way a person would write them; anything else is written as a matrix().
+    pub fn svg_string(&self) -> Option<String> {
+        if self.is_identity() {
+            return None;
+        }
+        let translate_str = format!("translate({}, {})", self.e, self.f);
+        if self.b == 0.0 && self.c == 0.0 && self.a == self.d {
+            return Some(match (self.e == 0.0 && self.f == 0.0, self.a == 1.0) {
+                (_, true) => translate_str,
+                (true, false) => format!("scale({})", self.a),
+                (false, false) => format!("{} scale({})", translate_str, self.a),
+            });
+        }
+        Some(format!("matrix({}, {}, {}, {}, {}, {})", self.a, self.b, self.c, self.d, self.e, self.f))
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+
+/// One piece of a Path. Each segment starts wherever the previous one ended.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Segment {
+    Line{to: Point},
+    Cubic{ctrl_1: Point, ctrl_2: Point, to: Point},
+    Quadratic{ctrl: Point, to: Point},
+    /// An elliptical arc, with the same fields (and meaning) as the SVG "A" command.
+    /// x_axis_rotation is in degrees.
+    Arc{radii: (Coord, Coord), x_axis_rotation: Coord, large_arc: bool, sweep: bool, to: Point},
+}
+
+/// The center parameterization of an arc (see the SVG spec, appendix F.6.5).
+struct ArcCenter {
+    center: Point,
+    radii: (Coord, Coord),
+    phi: Coord, // radians
+    theta_1: Coord, // radians
+    delta_theta: Coord, // radians; negative for counter-sweep
+}
+
+/// Number of pieces used when finding the length of a curve numerically.
+const LENGTH_STEPS: usize = 64;
+
+
+impl ArcCenter {
+    /// Returns None for arcs that SVG draws as a straight line (or not at all).
+    fn new(from: Point, radii: (Coord, Coord), x_axis_rotation: Coord, large_arc: bool, sweep: bool, to: Point) -> Option<Self> {
+        let (mut rx, mut ry) = (radii.0.abs(), radii.1.abs());
+        if rx == 0.0 || ry == 0.0 || from == to {
+            return None;
+        }
+        let phi = x_axis_rotation.to_radians();
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        let half_dx = (from.0 - to.0) / 2.0;
+        let half_dy = (from.1 - to.1) / 2.0;
+        let x1 = cos_phi * half_dx + sin_phi * half_dy;
+        let y1 = -sin_phi * half_dx + cos_phi * half_dy;
+
+        // --- radii that are too small get scaled up until they just fit ---
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        // --- find the center ---
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let sign = if large_arc == sweep {-1.0} else {1.0};
+        let coef = sign * (numerator / denominator).max(0.0).sqrt();
+        let cx1 = coef * rx * y1 / ry;
+        let cy1 = -coef * ry * x1 / rx;
+        let center = (
+            cos_phi * cx1 - sin_phi * cy1 + (from.0 + to.0) / 2.0,
+            sin_phi * cx1 + cos_phi * cy1 + (from.1 + to.1) / 2.0,
+        );
+
+        // --- find the angles ---
+        let u = ((x1 - cx1) / rx, (y1 - cy1) / ry);
+        let v = ((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+        let theta_1 = u.1.atan2(u.0);
+        let mut delta_theta = (v.1.atan2(v.0) - theta_1).rem_euclid(2.0 * std::f64::consts::PI);
+        if !sweep && delta_theta > 0.0 {
+            delta_theta -= 2.0 * std::f64::consts::PI;
+        }
+        Some(ArcCenter{center, radii: (rx, ry), phi, theta_1, delta_theta})
+    }
+
+    /// Returns the point on the ellipse at angle theta.
+    fn point_at_angle(&self, theta: Coord) -> Point {
+        let (sin_phi, cos_phi) = self.phi.sin_cos();
+        let (sin_t, cos_t) = theta.sin_cos();
+        let (rx, ry) = self.radii;
+        (
+            self.center.0 + rx * cos_phi * cos_t - ry * sin_phi * sin_t,
+            self.center.1 + rx * sin_phi * cos_t + ry * cos_phi * sin_t,
+        )
+    }
+
+    fn point_at(&self, t: Coord) -> Point {
+        self.point_at_angle(self.theta_1 + t * self.delta_theta)
+    }
+
+    fn derivative_at(&self, t: Coord) -> Point {
+        let (sin_phi, cos_phi) = self.phi.sin_cos();
+        let (sin_t, cos_t) = (self.theta_1 + t * self.delta_theta).sin_cos();
+        let (rx, ry) = self.radii;
+        (
+            (-rx * cos_phi * sin_t - ry * sin_phi * cos_t) * self.delta_theta,
+            (-rx * sin_phi * sin_t + ry * cos_phi * cos_t) * self.delta_theta,
+        )
+    }
+
+    /// Returns the points on the arc that are furthest left, right, up or down (if
+    /// the arc gets that far around).
+    fn extreme_points(&self) -> Vec<Point> {
+        let (sin_phi, cos_phi) = self.phi.sin_cos();
+        let (rx, ry) = self.radii;
+        let theta_x = (-ry * sin_phi).atan2(rx * cos_phi);
+        let theta_y = (ry * cos_phi).atan2(rx * sin_phi);
+        let pi = std::f64::consts::PI;
+        [theta_x, theta_x + pi, theta_y, theta_y + pi].into_iter()
+            .filter(|theta| {
+                if self.delta_theta >= 0.0 {
+                    (theta - self.theta_1).rem_euclid(2.0 * pi) <= self.delta_theta
+                } else {
+                    (self.theta_1 - theta).rem_euclid(2.0 * pi) <= -self.delta_theta
+                }
+            })
+            .map(|theta| self.point_at_angle(theta))
+            .collect()
+    }
+}
+
+
+/// Returns the values of t in (0,1) where a*t^2 + b*t + c is zero.
+fn roots_in_unit_interval(a: Coord, b: Coord, c: Coord) -> Vec<Coord> {
+    const EPSILON: Coord = 1e-12;
+    let roots = if a.abs() < EPSILON {
+        if b.abs() < EPSILON {vec![]} else {vec![-c / b]}
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            vec![]
+        } else {
+            let sqrt_d = discriminant.sqrt();
+            vec![(-b + sqrt_d) / (2.0 * a), (-b - sqrt_d) / (2.0 * a)]
+        }
+    };
+    roots.into_iter().filter(|t| *t > 0.0 && *t < 1.0).collect()
+}
+
+/// Returns the point part way (t) from p1 to p2.
+fn lerp(p1: Point, p2: Point, t: Coord) -> Point {
+    (p1.0 + (p2.0 - p1.0) * t, p1.1 + (p2.1 - p1.1) * t)
+}
+
+
+impl Segment {
+    /// Returns the point where this segment ends.
+    pub fn end(&self) -> Point {
+        match self {
+            Segment::Line{to} => *to,
+            Segment::Cubic{to, ..} => *to,
+            Segment::Quadratic{to, ..} => *to,
+            Segment::Arc{to, ..} => *to,
+        }
+    }
+
+    /// Returns the center form of this segment if it's an arc that curves.
+    fn arc_center(&self, from: Point) -> Option<ArcCenter> {
+        match self {
+            Segment::Arc{radii, x_axis_rotation, large_arc, sweep, to} =>
+                ArcCenter::new(from, *radii, *x_axis_rotation, *large_arc, *sweep, *to),
+            _ => None,
+        }
+    }
+
+    /// Returns the point at t (from 0 to 1) along this segment, which starts at from.
+    pub fn point_at(&self, from: Point, t: Coord) -> Point {
+        match self {
+            Segment::Line{to} => lerp(from, *to, t),
+            Segment::Cubic{ctrl_1, ctrl_2, to} => {
+                let mt = 1.0 - t;
+                let (w0, w1, w2, w3) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+                (
+                    w0 * from.0 + w1 * ctrl_1.0 + w2 * ctrl_2.0 + w3 * to.0,
+                    w0 * from.1 + w1 * ctrl_1.1 + w2 * ctrl_2.1 + w3 * to.1,
+                )
+            },
+            Segment::Quadratic{ctrl, to} => {
+                let mt = 1.0 - t;
+                let (w0, w1, w2) = (mt * mt, 2.0 * mt * t, t * t);
+                (w0 * from.0 + w1 * ctrl.0 + w2 * to.0, w0 * from.1 + w1 * ctrl.1 + w2 * to.1)
+            },
+            Segment::Arc{to, ..} => match self.arc_center(from) {
+                None => lerp(from, *to, t),
+                Some(arc) => arc.point_at(t),
+            },
+        }
+    }
+
+    /// Returns the derivative (with respect to t) at t along this segment.
+    fn derivative_at(&self, from: Point, t: Coord) -> Point {
+        match self {
+            Segment::Line{to} => (to.0 - from.0, to.1 - from.1),
+            Segment::Cubic{ctrl_1, ctrl_2, to} => {
+                let mt = 1.0 - t;
+                let (w0, w1, w2) = (3.0 * mt * mt, 6.0 * mt * t, 3.0 * t * t);
+                (
+                    w0 * (ctrl_1.0 - from.0) + w1 * (ctrl_2.0 - ctrl_1.0) + w2 * (to.0 - ctrl_2.0),
+                    w0 * (ctrl_1.1 - from.1) + w1 * (ctrl_2.1 - ctrl_1.1) + w2 * (to.1 - ctrl_2.1),
+                )
+            },
+            Segment::Quadratic{ctrl, to} => {
+                let mt = 1.0 - t;
+                (
+                    2.0 * mt * (ctrl.0 - from.0) + 2.0 * t * (to.0 - ctrl.0),
+                    2.0 * mt * (ctrl.1 - from.1) + 2.0 * t * (to.1 - ctrl.1),
+                )
+            },
+            Segment::Arc{to, ..} => match self.arc_center(from) {
+                None => (to.0 - from.0, to.1 - from.1),
+                Some(arc) => arc.derivative_at(t),
+            },
+        }
+    }
+
+    /// Returns the direction (as a vector of length 1) this segment is heading at t. A
+    /// segment with no length at all returns (0,0).
+    pub fn tangent_at(&self, from: Point, t: Coord) -> Point {
+        let mut d = self.derivative_at(from, t);
+        if d.0.hypot(d.1) < 1e-12 {
+            // curves can stop moving at an end (like when a control point is ON the end),
+            // so look at where it goes from just beside that point instead
+            let (t0, t1) = if t < 0.5 {(t, t + 1e-6)} else {(t - 1e-6, t)};
+            let (p0, p1) = (self.point_at(from, t0), self.point_at(from, t1));
+            d = (p1.0 - p0.0, p1.1 - p0.1);
+        }
+        let len = d.0.hypot(d.1);
+        if len == 0.0 {(0.0, 0.0)} else {(d.0 / len, d.1 / len)}
+    }
+
+    /// Returns the length of this segment. Curves are measured numerically (using
+    /// Simpson's rule), which is very close but not exact.
+    pub fn length(&self, from: Point) -> Coord {
+        match self {
+            Segment::Line{to} => (to.0 - from.0).hypot(to.1 - from.1),
+            _ => {
+                let speed = |t: Coord| {
+                    let d = self.derivative_at(from, t);
+                    d.0.hypot(d.1)
+                };
+                let h = 1.0 / LENGTH_STEPS as Coord;
+                let inner: Coord = (1..LENGTH_STEPS)
+                    .map(|i| speed(i as Coord * h) * if i % 2 == 1 {4.0} else {2.0})
+                    .sum();
+                (speed(0.0) + inner + speed(1.0)) * h / 3.0
+            },
+        }
+    }
+
+    /// Returns the exact bounding box of this segment, which starts at from. (Control
+    /// points are only included where the curve actually reaches them.)
+    pub fn bbox(&self, from: Point) -> Rect {
+        let mut points: Vec<Point> = vec![from, self.end()];
+        match self {
+            Segment::Line{..} => {},
+            Segment::Cubic{ctrl_1, ctrl_2, to} => {
+                let axis_roots = |p0: Coord, p1: Coord, p2: Coord, p3: Coord| roots_in_unit_interval(
+                    -p0 + 3.0 * p1 - 3.0 * p2 + p3,
+                    2.0 * (p0 - 2.0 * p1 + p2),
+                    p1 - p0,
+                );
+                let ts = axis_roots(from.0, ctrl_1.0, ctrl_2.0, to.0).into_iter()
+                    .chain(axis_roots(from.1, ctrl_1.1, ctrl_2.1, to.1));
+                points.extend(ts.map(|t| self.point_at(from, t)));
+            },
+            Segment::Quadratic{ctrl, to} => {
+                let axis_roots = |p0: Coord, p1: Coord, p2: Coord| roots_in_unit_interval(
+                    0.0,
+                    p0 - 2.0 * p1 + p2,
+                    p1 - p0,
+                );
+                let ts = axis_roots(from.0, ctrl.0, to.0).into_iter()
+                    .chain(axis_roots(from.1, ctrl.1, to.1));
+                points.extend(ts.map(|t| self.point_at(from, t)));
+            },
+            Segment::Arc{..} => {
+                if let Some(arc) = self.arc_center(from) {
+                    points.extend(arc.extreme_points());
+                }
+            },
+        }
+        bbox_of_points(&points)
+    }
+
+    /// Returns the SVG path command for this segment.
+    pub fn svg_d(&self) -> String {
+        match self {
+            Segment::Line{to} => format!("L {} {}", to.0, to.1),
+            Segment::Cubic{ctrl_1, ctrl_2, to} => format!(
+                "C {} {}, {} {}, {} {}", ctrl_1.0, ctrl_1.1, ctrl_2.0, ctrl_2.1, to.0, to.1
+            ),
+            Segment::Quadratic{ctrl, to} => format!("Q {} {}, {} {}", ctrl.0, ctrl.1, to.0, to.1),
+            Segment::Arc{radii, x_axis_rotation, large_arc, sweep, to} => format!(
+                "A {} {} {} {} {} {} {}",
+                radii.0, radii.1, x_axis_rotation, *large_arc as u8, *sweep as u8, to.0, to.1
+            ),
+        }
+    }
+}
+
+
+/// Returns the point where the line segment from a1 to a2 crosses the one from b1 to b2, or
+/// None if they don't. Segments that lie along the same line are treated as not crossing.
+pub fn segment_intersection(a1: Point, a2: Point, b1: Point, b2: Point) -> Option<Point> {
+    let (ax, ay) = (a2.0 - a1.0, a2.1 - a1.1);
+    let (bx, by) = (b2.0 - b1.0, b2.1 - b1.1);
+    let denominator = ax * by - ay * bx;
+    if denominator.abs() < 1e-12 {
+        return None; // parallel
+    }
+    let (dx, dy) = (b1.0 - a1.0, b1.1 - a1.1);
+    let t = (dx * by - dy * bx) / denominator;
+    let u = (dx * ay - dy * ax) / denominator;
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some(lerp(a1, a2, t))
+    } else {
+        None
+    }
+}
+
+
+/// Returns the smallest Rect that covers all of the points. There must be at least one.
+fn bbox_of_points(points: &[Point]) -> Rect {
+    assert!(!points.is_empty());
+    Rect::new_ltrb(
+        points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min),
+        points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min),
+        points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max),
+        points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max),
+    )
+}
+
+
+/// A shape made of line and curve segments, like the SVG path element draws. It is built
+/// up by starting at a point and then adding segments:
+///
+/// ```
+/// # use prog_draw::geometry::Path;
+/// let path = Path::new((0.0, 0.0)).line_to((10.0, 0.0)).quad_to((20.0, 0.0), (20.0, 10.0));
+/// assert_eq!(path.svg_d(), "M 0 0 L 10 0 Q 20 0, 20 10");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    start: Point,
+    segments: Vec<Segment>,
+    closed: bool,
+}
+
+impl Path {
+    /// Starts a new path at the given point.
+    pub fn new(start: Point) -> Self {
+        Path{start, segments: Vec::new(), closed: false}
+    }
+
+    pub fn line_to(mut self, to: Point) -> Self {
+        self.segments.push(Segment::Line{to});
+        self
+    }
+
+    pub fn cubic_to(mut self, ctrl_1: Point, ctrl_2: Point, to: Point) -> Self {
+        self.segments.push(Segment::Cubic{ctrl_1, ctrl_2, to});
+        self
+    }
+
+    pub fn quad_to(mut self, ctrl: Point, to: Point) -> Self {
+        self.segments.push(Segment::Quadratic{ctrl, to});
+        self
+    }
+
+    /// Adds an elliptical arc. The fields mean the same as in the SVG "A" command.
+    pub fn arc_to(mut self, radii: (Coord, Coord), x_axis_rotation: Coord, large_arc: bool, sweep: bool, to: Point) -> Self {
+        self.segments.push(Segment::Arc{radii, x_axis_rotation, large_arc, sweep, to});
+        self
+    }
+
+    /// Closes the path with a straight line back to the start.
+    pub fn close(mut self) -> Self {
+        self.closed = true;
+        self
+    }
+
+    pub fn start(&self) -> Point {self.start}
+    pub fn is_closed(&self) -> bool {self.closed}
+
+    /// Returns the point where the path ends (which is the start if it's closed).
+    pub fn end(&self) -> Point {
+        match (self.closed, self.segments.last()) {
+            (false, Some(segment)) => segment.end(),
+            _ => self.start,
+        }
+    }
+
+    /// Returns each segment along with the point it starts from. A closed path includes
+    /// the line back to the start.
+    pub fn segments(&self) -> Vec<(Point, Segment)> {
+        let mut answer: Vec<(Point, Segment)> = Vec::with_capacity(self.segments.len() + 1);
+        let mut from = self.start;
+        for segment in self.segments.iter() {
+            answer.push((from, *segment));
+            from = segment.end();
+        }
+        if self.closed && from != self.start {
+            answer.push((from, Segment::Line{to: self.start}));
+        }
+        answer
+    }
+
+    /// Finds which segment t (from 0 to 1 along the whole path) falls in, and how far
+    /// along that segment it is. Each segment gets an equal share of t.
+    fn locate(&self, t: Coord) -> Option<(Point, Segment, Coord)> {
+        let segments = self.segments();
+        if segments.is_empty() {
+            return None;
+        }
+        let scaled = t.clamp(0.0, 1.0) * segments.len() as Coord;
+        let idx = (scaled.floor() as usize).min(segments.len() - 1);
+        let (from, segment) = segments[idx];
+        Some((from, segment, scaled - idx as Coord))
+    }
+
+    /// Returns the point at t (from 0 to 1) along the path. Each segment gets an equal
+    /// share of t, so this is NOT the same as the point t of the way along the length.
+    pub fn point_at(&self, t: Coord) -> Point {
+        match self.locate(t) {
+            None => self.start,
+            Some((from, segment, local_t)) => segment.point_at(from, local_t),
+        }
+    }
+
+    /// Returns the direction (as a vector of length 1) the path is heading at t (from 0
+    /// to 1, the same as for point_at()).
+    pub fn tangent_at(&self, t: Coord) -> Point {
+        match self.locate(t) {
+            None => (0.0, 0.0),
+            Some((from, segment, local_t)) => segment.tangent_at(from, local_t),
+        }
+    }
+
+    /// Returns the total length of the path.
+    pub fn length(&self) -> Coord {
+        self.segments().iter()
+            .map(|(from, segment)| segment.length(*from))
+            .sum()
+    }
+
+    /// Returns a new Path that is the old one but translated by (dx,dy).
+    pub fn translated(&self, dx: Coord, dy: Coord) -> Path {
+        let shift = |p: Point| (p.0 + dx, p.1 + dy);
+        let segments = self.segments.iter()
+            .map(|segment| match *segment {
+                Segment::Line{to} => Segment::Line{to: shift(to)},
+                Segment::Cubic{ctrl_1, ctrl_2, to} => Segment::Cubic{ctrl_1: shift(ctrl_1), ctrl_2: shift(ctrl_2), to: shift(to)},
+                Segment::Quadratic{ctrl, to} => Segment::Quadratic{ctrl: shift(ctrl), to: shift(to)},
+                Segment::Arc{radii, x_axis_rotation, large_arc, sweep, to} =>
+                    Segment::Arc{radii, x_axis_rotation, large_arc, sweep, to: shift(to)},
+            })
+            .collect();
+        Path{start: shift(self.start), segments, closed: self.closed}
+    }
+
+    /// Returns points along the path, which can be joined with straight lines to get
+    /// something close to it. Each segment is split into steps pieces (except straight
+    /// lines, which don't need splitting).
+    pub fn flattened(&self, steps: usize) -> Vec<Point> {
+        let mut answer: Vec<Point> = vec![self.start];
+        for (from, segment) in self.segments() {
+            match segment {
+                Segment::Line{to} => answer.push(to),
+                _ => answer.extend((1..=steps).map(|i| segment.point_at(from, i as Coord / steps as Coord))),
+            }
+        }
+        answer
+    }
+
+    /// Returns the exact bounding box of the path.
+    pub fn bbox(&self) -> Rect {
+        self.segments().iter()
+            .map(|(from, segment)| segment.bbox(*from))
+            .fold(Rect::new_ltwh(self.start.0, self.start.1, 0.0, 0.0), |r1, r2| r1.cover(&r2))
+    }
+
+    /// Returns the path as the value of the "d" attribute of an SVG path element.
+    pub fn svg_d(&self) -> String {
+        let mut answer = format!("M {} {}", self.start.0, self.start.1);
+        for segment in self.segments.iter() {
+            answer.push(' ');
+            answer.push_str(&segment.svg_d());
+        }
+        if self.closed {
+            answer.push_str(" Z");
+        }
+        answer
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(p1: Point, p2: Point) {
+        assert!((p1.0 - p2.0).abs() < 1e-9 && (p1.1 - p2.1).abs() < 1e-9, "{:?} != {:?}", p1, p2);
+    }
+
+    #[test]
+    fn test_compose_order() {
+        let t = Transform::translate(10.0, 0.0).then(&Transform::scale(2.0));
+        assert_near(t.apply((1.0, 1.0)), (22.0, 2.0));
+        let t = Transform::translate(10.0, 0.0).compose(&Transform::scale(2.0));
+        assert_near(t.apply((1.0, 1.0)), (12.0, 2.0));
+        assert_eq!(t.svg_string().unwrap(), "translate(10, 0) scale(2)");
+    }
+
+    #[test]
+    fn test_rotate_and_inverse() {
+        let t = Transform::rotate_about(90.0, (1.0, 1.0));
+        assert_near(t.apply((2.0, 1.0)), (1.0, 2.0));
+        let back = t.then(&t.inverse().unwrap());
+        assert_near(back.apply((3.0, 4.0)), (3.0, 4.0));
+        assert!(Transform::scale(0.0).inverse().is_none());
+    }
+
+    #[test]
+    fn test_transform_rect() {
+        let rect = Rect::new_ltwh(10.0, 10.0, 20.0, 10.0);
+        let scaled = Transform::scale(2.0).transform_rect(&rect);
+        assert_near((scaled.left(), scaled.top()), (20.0, 20.0));
+        assert_near((scaled.width(), scaled.height()), (40.0, 20.0));
+        let rotated = Transform::rotate(90.0).transform_rect(&rect);
+        assert_near((rotated.left(), rotated.top()), (-20.0, 10.0));
+        assert_near((rotated.width(), rotated.height()), (10.0, 20.0));
+    }
+
+    #[test]
+    fn test_bbox_cover_identity() {
+        let rect = Rect::new_ltwh(10.0, 10.0, 20.0, 10.0);
+        assert_eq!(BBox::Empty.cover(&rect.into()), BBox::Area(rect));
+        assert_eq!(BBox::from(rect).cover(&BBox::Empty), BBox::Area(rect));
+        let none: Vec<BBox> = Vec::new();
+        assert!(none.into_iter().collect::<BBox>().is_empty());
+        let both: BBox = [rect.into(), Rect::new_ltwh(0.0, 0.0, 1.0, 1.0).into()].into_iter().collect();
+        assert_eq!(both, BBox::Area(Rect::new_ltrb(0.0, 0.0, 30.0, 20.0)));
+    }
+
+    #[test]
+    fn test_bbox_intersection_and_contains() {
+        let r1 = Rect::new_ltwh(0.0, 0.0, 10.0, 10.0);
+        let r2 = Rect::new_ltwh(5.0, 5.0, 10.0, 10.0);
+        assert_eq!(r1.intersection(&r2), BBox::Area(Rect::new_ltwh(5.0, 5.0, 5.0, 5.0)));
+        assert!(r1.intersection(&Rect::new_ltwh(20.0, 0.0, 1.0, 1.0)).is_empty());
+        assert!(r1.contains_point((10.0, 0.0)));
+        assert!(!r1.contains_point((10.1, 0.0)));
+        assert!(BBox::from(r1).contains(&BBox::Empty));
+        assert!(!BBox::Empty.contains_point((0.0, 0.0)));
+        let inflated = r1.inflated(&Margins::uniform(2.0));
+        assert!(inflated.contains(&r1));
+        assert_eq!(inflated.deflated(&Margins::uniform(2.0)), BBox::Area(r1));
+        assert!(r1.deflated(&Margins::uniform(6.0)).is_empty());
+    }
+
+    #[test]
+    fn test_path_bbox_uses_curve_not_control_points() {
+        // a cubic whose control points stick out well past where the curve reaches
+        let path = Path::new((0.0, 0.0)).cubic_to((0.0, 40.0), (30.0, 40.0), (30.0, 0.0));
+        let bbox = path.bbox();
+        assert_near((bbox.left(), bbox.right()), (0.0, 30.0));
+        assert_near((bbox.top(), bbox.bottom()), (0.0, 30.0));
+        let quad = Path::new((0.0, 0.0)).quad_to((10.0, 20.0), (20.0, 0.0));
+        assert_near((quad.bbox().bottom(), quad.point_at(0.5).1), (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_path_arc() {
+        // a half circle of radius 10 from (0,0) to (20,0), bulging upward
+        let path = Path::new((0.0, 0.0)).arc_to((10.0, 10.0), 0.0, false, true, (20.0, 0.0));
+        let bbox = path.bbox();
+        assert_near((bbox.left(), bbox.top()), (0.0, -10.0));
+        assert_near((bbox.right(), bbox.bottom()), (20.0, 0.0));
+        assert_near(path.point_at(0.5), (10.0, -10.0));
+        assert_near(path.tangent_at(0.5), (1.0, 0.0));
+        assert!((path.length() - 10.0 * std::f64::consts::PI).abs() < 1e-6);
+        assert_eq!(path.svg_d(), "M 0 0 A 10 10 0 0 1 20 0");
+    }
+
+    #[test]
+    fn test_path_lines() {
+        let path = Path::new((0.0, 0.0)).line_to((3.0, 0.0)).line_to((3.0, 4.0)).close();
+        assert!((path.length() - 12.0).abs() < 1e-9);
+        assert_near(path.tangent_at(0.5), (0.0, 1.0));
+        assert_eq!(path.svg_d(), "M 0 0 L 3 0 L 3 4 Z");
+    }
 }
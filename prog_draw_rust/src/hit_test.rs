@@ -0,0 +1,58 @@
+//
+// Hit testing finds what is under a point in a laid-out drawing, using the same bboxes
+// that SvgPositioned gives for laying it out. This lets a host use its own pointer events
+// instead of relying on handlers written into the SVG.
+//
+
+use crate::geometry::{BBox, Point};
+
+
+/// Identifies one thing in a drawing: what kind of thing it is and its id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementRef {
+    pub kind: &'static str,
+    pub id: String,
+}
+
+impl ElementRef {
+    pub fn new(kind: &'static str, id: &str) -> Self {
+        ElementRef{kind, id: id.to_string()}
+    }
+}
+
+
+/// Something that can be hit, along with the area it covers (normally its get_bbox(),
+/// moved to where it is drawn).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitRegion {
+    pub element: ElementRef,
+    pub bbox: BBox,
+}
+
+
+/// Returns the element under the point, or None if there isn't one. The regions go from
+/// back to front, so where they overlap the one that comes last wins.
+pub fn hit_test(regions: &[HitRegion], point: Point) -> Option<ElementRef> {
+    regions.iter().rev()
+        .find(|region| region.bbox.contains_point(point))
+        .map(|region| region.element.clone())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Rect;
+
+    #[test]
+    fn test_hit_test() {
+        let regions = [
+            HitRegion{element: ElementRef::new("back", "a"), bbox: Rect::new_ltrb(0.0, 0.0, 10.0, 10.0).into()},
+            HitRegion{element: ElementRef::new("front", "b"), bbox: Rect::new_ltrb(5.0, 5.0, 15.0, 15.0).into()},
+            HitRegion{element: ElementRef::new("empty", "c"), bbox: BBox::Empty},
+        ];
+        assert_eq!(hit_test(&regions, (2.0, 2.0)), Some(ElementRef::new("back", "a")));
+        assert_eq!(hit_test(&regions, (7.0, 7.0)), Some(ElementRef::new("front", "b")));
+        assert_eq!(hit_test(&regions, (20.0, 2.0)), None);
+    }
+}
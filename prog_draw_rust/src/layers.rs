@@ -0,0 +1,131 @@
+//
+// A LayeredScene draws what is in it one named layer at a time, from the back to the
+// front. Anything put on a later layer (like a highlight on the overlay) ends up on top,
+// no matter which component added it or in what order things were added.
+//
+
+use crate::geometry::{BBox, Transform};
+use crate::svg_render::{OwnedGroup, SvgPositioned};
+use crate::svg_writer::{Attributes, Renderable, TagWriter, TagWriterError};
+
+
+/// The layers of a LayeredScene, from back to front.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    Background,
+    Edges,
+    Nodes,
+    Labels,
+    Overlay,
+}
+
+impl Layer {
+    /// Every layer, from back to front.
+    pub const ALL: [Layer; 5] = [Layer::Background, Layer::Edges, Layer::Nodes, Layer::Labels, Layer::Overlay];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layer::Background => "background",
+            Layer::Edges => "edges",
+            Layer::Nodes => "nodes",
+            Layer::Labels => "labels",
+            Layer::Overlay => "overlay",
+        }
+    }
+}
+
+
+/// A trait for components that draw parts of themselves on different layers.
+pub trait Layered {
+    /// Adds the parts of this to the layers they belong on, moved by the transform.
+    fn add_to_layers<'a>(&'a self, scene: &mut LayeredScene<'a>, transform: Transform);
+}
+
+
+#[derive(Default)]
+pub struct LayeredScene<'a> {
+    /// One group for each layer, in the same order as Layer::ALL.
+    layers: [OwnedGroup<'a>; Layer::ALL.len()],
+}
+
+impl<'a> LayeredScene<'a> {
+    pub fn new() -> Self {
+        LayeredScene::default()
+    }
+
+    /// Adds the item to the front of a layer.
+    pub fn add(&mut self, layer: Layer, item: impl SvgPositioned + 'a) {
+        self.layers[layer as usize].add(item);
+    }
+
+    /// Adds the item, moved by the transform, to the front of a layer.
+    pub fn add_transformed(&mut self, layer: Layer, item: impl SvgPositioned + 'a, transform: Transform) {
+        self.add(layer, OwnedGroup::item_with_transform(item, transform));
+    }
+
+    /// Lets a component add its parts to whichever layers it wants.
+    pub fn add_layered(&mut self, component: &'a impl Layered, transform: Transform) {
+        component.add_to_layers(self, transform);
+    }
+
+    /// Returns the group holding everything on a layer.
+    pub fn layer(&self, layer: Layer) -> &OwnedGroup<'a> {
+        &self.layers[layer as usize]
+    }
+}
+
+impl<'a> Renderable for LayeredScene<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        for layer in Layer::ALL {
+            let group = self.layer(layer);
+            if !group.items.is_empty() {
+                tag_writer.begin_tag("g", Attributes::from([("class", format!("layer-{}", layer.name()))]))?;
+                group.items.render(tag_writer)?;
+                tag_writer.end_tag("g")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> SvgPositioned for LayeredScene<'a> {
+    fn get_bbox(&self) -> BBox {
+        self.layers.iter().map(|x| x.get_bbox()).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Rect;
+    use crate::style::Style;
+    use crate::svg_render::Circle;
+    use crate::svg_writer::TagWriterImpl;
+
+    struct Pin;
+
+    impl Layered for Pin {
+        fn add_to_layers<'a>(&'a self, scene: &mut LayeredScene<'a>, transform: Transform) {
+            scene.add_transformed(Layer::Overlay, Circle::new((0.0, 0.0), 1.0, Style::filled("#FF0000")), transform);
+            scene.add_transformed(Layer::Edges, Circle::new((0.0, 0.0), 2.0, Style::filled("#00FF00")), transform);
+        }
+    }
+
+    #[test]
+    fn test_layers_draw_back_to_front() {
+        let mut scene = LayeredScene::new();
+        scene.add_layered(&Pin, Transform::translate(10.0, 0.0));
+        scene.add(Layer::Nodes, Circle::new((0.0, 0.0), 1.0, Style::filled("#0000FF")));
+        assert_eq!(scene.get_bbox(), Rect::new_ltrb(-1.0, -2.0, 12.0, 2.0).into());
+
+        let mut output: Vec<u8> = Vec::new();
+        scene.render(&mut TagWriterImpl::new(&mut output)).unwrap();
+        let svg = String::from_utf8(output).unwrap();
+        let position = |text: &str| svg.find(text).unwrap();
+        assert!(position("layer-edges") < position("layer-nodes"));
+        assert!(position("#00FF00") < position("#0000FF"));
+        assert!(position("#0000FF") < position("#FF0000"));
+        assert!(!svg.contains("layer-labels"));
+    }
+}
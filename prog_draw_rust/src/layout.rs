@@ -0,0 +1,426 @@
+//
+// Containers that position their children by looking at the children's bounding boxes,
+// so things like "these side by side", "this centered above that", or "this with some
+// room around it" can be written down instead of worked out by hand. Each container
+// moves its children (with a translate) but never resizes them. Children that are empty
+// take up no room and aren't moved.
+//
+
+use crate::geometry::{BBox, Coord, Margins, Point, Rect, Transform};
+use crate::svg_render::{Group, SvgPositioned};
+use crate::svg_writer::{Renderable, TagWriter, TagWriterError};
+
+
+/// Where something goes within the room it has, along one direction. For horizontal
+/// directions Start is the left; for vertical directions Start is the top.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+impl Align {
+    /// Returns the position of this part of the span from start to end.
+    fn pick(&self, start: Coord, end: Coord) -> Coord {
+        match self {
+            Align::Start => start,
+            Align::Center => (start + end) / 2.0,
+            Align::End => end,
+        }
+    }
+}
+
+/// Names one of 9 points on a box: (horizontal, vertical). For instance, (Center, End)
+/// is the middle of the bottom edge.
+pub type BoxPoint = (Align, Align);
+
+/// Returns the named point of the rect.
+pub fn box_point(rect: &Rect, point: BoxPoint) -> Point {
+    (point.0.pick(rect.left(), rect.right()), point.1.pick(rect.top(), rect.bottom()))
+}
+
+
+type Child<'a> = Box<dyn SvgPositioned + 'a>;
+
+/// Draws each child moved by its offset.
+fn render_placed(tag_writer: &mut dyn TagWriter, children: &[Child], offsets: &[Point]) -> Result<(), TagWriterError> {
+    for (child, (dx, dy)) in children.iter().zip(offsets) {
+        if *dx == 0.0 && *dy == 0.0 {
+            child.render(tag_writer)?;
+        } else {
+            Group::item_with_transform(child.as_ref(), Transform::translate(*dx, *dy)).render(tag_writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the bbox covering each child moved by its offset.
+fn placed_bbox(children: &[Child], offsets: &[Point]) -> BBox {
+    children.iter()
+        .zip(offsets)
+        .map(|(child, (dx, dy))| child.get_bbox().translated(*dx, *dy))
+        .collect()
+}
+
+/// Returns the offsets that line the children up one after another along one direction,
+/// and aligns them in the other. The direction is picked out by span(), which returns
+/// the (start, end) of a rect along it, and cross(), which does the same for the other
+/// direction. It returns the offsets as (along, across).
+fn line_up(
+    children: &[Child],
+    spacing: Coord,
+    align: Align,
+    span: fn(&Rect) -> (Coord, Coord),
+    cross: fn(&Rect) -> (Coord, Coord),
+) -> Vec<Point> {
+    let rects: Vec<Option<Rect>> = children.iter().map(|x| x.get_bbox().rect()).collect();
+    let thickness = rects.iter()
+        .flatten()
+        .map(|rect| cross(rect).1 - cross(rect).0)
+        .fold(0.0, Coord::max);
+    let mut next_start = 0.0;
+    rects.iter()
+        .map(|rect| match rect {
+            None => (0.0, 0.0),
+            Some(rect) => {
+                let (start, end) = span(rect);
+                let (cross_start, cross_end) = cross(rect);
+                let along = next_start - start;
+                next_start += end - start + spacing;
+                let across = align.pick(0.0, thickness - (cross_end - cross_start)) - cross_start;
+                (along, across)
+            },
+        })
+        .collect()
+}
+
+fn horizontal_span(rect: &Rect) -> (Coord, Coord) {(rect.left(), rect.right())}
+fn vertical_span(rect: &Rect) -> (Coord, Coord) {(rect.top(), rect.bottom())}
+
+
+/// Lays the children out left to right, starting at x = 0 with their tops (or centers
+/// or bottoms, as picked by align) lined up below y = 0.
+#[derive(Default)]
+pub struct HStack<'a> {
+    children: Vec<Child<'a>>,
+    spacing: Coord,
+    align: Align,
+}
+
+impl<'a> HStack<'a> {
+    pub fn new(spacing: Coord, align: Align) -> Self {
+        HStack{children: Vec::new(), spacing, align}
+    }
+
+    pub fn add(&mut self, item: impl SvgPositioned + 'a) {
+        self.children.push(Box::new(item));
+    }
+
+    fn offsets(&self) -> Vec<Point> {
+        line_up(&self.children, self.spacing, self.align, horizontal_span, vertical_span)
+    }
+}
+
+impl<'a> Renderable for HStack<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        render_placed(tag_writer, &self.children, &self.offsets())
+    }
+}
+
+impl<'a> SvgPositioned for HStack<'a> {
+    fn get_bbox(&self) -> BBox {
+        placed_bbox(&self.children, &self.offsets())
+    }
+}
+
+
+/// Lays the children out top to bottom, starting at y = 0 with their left sides (or
+/// centers or right sides, as picked by align) lined up to the right of x = 0.
+#[derive(Default)]
+pub struct VStack<'a> {
+    children: Vec<Child<'a>>,
+    spacing: Coord,
+    align: Align,
+}
+
+impl<'a> VStack<'a> {
+    pub fn new(spacing: Coord, align: Align) -> Self {
+        VStack{children: Vec::new(), spacing, align}
+    }
+
+    pub fn add(&mut self, item: impl SvgPositioned + 'a) {
+        self.children.push(Box::new(item));
+    }
+
+    fn offsets(&self) -> Vec<Point> {
+        line_up(&self.children, self.spacing, self.align, vertical_span, horizontal_span)
+            .into_iter()
+            .map(|(along, across)| (across, along))
+            .collect()
+    }
+}
+
+impl<'a> Renderable for VStack<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        render_placed(tag_writer, &self.children, &self.offsets())
+    }
+}
+
+impl<'a> SvgPositioned for VStack<'a> {
+    fn get_bbox(&self) -> BBox {
+        placed_bbox(&self.children, &self.offsets())
+    }
+}
+
+
+/// Lays the children out in rows (filling each row before starting the next), with each
+/// column as wide as its widest child and each row as tall as its tallest. The top left
+/// cell starts at (0, 0).
+pub struct Grid<'a> {
+    children: Vec<Child<'a>>,
+    columns: usize,
+    spacing: (Coord, Coord),
+    cell_align: BoxPoint,
+}
+
+impl<'a> Grid<'a> {
+    /// Create a Grid with this many columns, and the given (horizontal, vertical) space
+    /// between the cells. Children go in the top left of their cells.
+    pub fn new(columns: usize, spacing: (Coord, Coord)) -> Self {
+        assert!(columns > 0, "A grid needs at least one column.");
+        Grid{children: Vec::new(), columns, spacing, cell_align: (Align::Start, Align::Start)}
+    }
+
+    pub fn add(&mut self, item: impl SvgPositioned + 'a) {
+        self.children.push(Box::new(item));
+    }
+
+    /// Call this to choose where each child goes within its cell.
+    pub fn set_cell_align(&mut self, cell_align: BoxPoint) {
+        self.cell_align = cell_align;
+    }
+
+    fn offsets(&self) -> Vec<Point> {
+        let rects: Vec<Option<Rect>> = self.children.iter().map(|x| x.get_bbox().rect()).collect();
+        let rows = self.children.len().div_ceil(self.columns);
+
+        // --- find how big each column and row is ---
+        let mut widths: Vec<Coord> = vec![0.0; self.columns];
+        let mut heights: Vec<Coord> = vec![0.0; rows];
+        for (i, rect) in rects.iter().enumerate() {
+            if let Some(rect) = rect {
+                let (row, column) = (i / self.columns, i % self.columns);
+                widths[column] = widths[column].max(rect.width());
+                heights[row] = heights[row].max(rect.height());
+            }
+        }
+        let starts = |sizes: &[Coord], spacing: Coord| -> Vec<Coord> {
+            sizes.iter()
+                .scan(0.0, |next, size| {
+                    let start = *next;
+                    *next += size + spacing;
+                    Some(start)
+                })
+                .collect()
+        };
+        let lefts = starts(&widths, self.spacing.0);
+        let tops = starts(&heights, self.spacing.1);
+
+        // --- move each child into its cell ---
+        rects.iter().enumerate()
+            .map(|(i, rect)| match rect {
+                None => (0.0, 0.0),
+                Some(rect) => {
+                    let (row, column) = (i / self.columns, i % self.columns);
+                    let cell = Rect::new_ltwh(lefts[column], tops[row], widths[column], heights[row]);
+                    let room = Rect::new_ltrb(
+                        cell.left(),
+                        cell.top(),
+                        cell.right() - rect.width(),
+                        cell.bottom() - rect.height(),
+                    );
+                    let (x, y) = box_point(&room, self.cell_align);
+                    (x - rect.left(), y - rect.top())
+                },
+            })
+            .collect()
+    }
+}
+
+impl<'a> Renderable for Grid<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        render_placed(tag_writer, &self.children, &self.offsets())
+    }
+}
+
+impl<'a> SvgPositioned for Grid<'a> {
+    fn get_bbox(&self) -> BBox {
+        placed_bbox(&self.children, &self.offsets())
+    }
+}
+
+
+/// Leaves room around a child: it is drawn unchanged, but its bbox is grown by the
+/// margins.
+pub struct Padding<'a> {
+    child: Child<'a>,
+    margins: Margins,
+}
+
+impl<'a> Padding<'a> {
+    pub fn new(item: impl SvgPositioned + 'a, margins: Margins) -> Self {
+        Padding{child: Box::new(item), margins}
+    }
+}
+
+impl<'a> Renderable for Padding<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        self.child.render(tag_writer)
+    }
+}
+
+impl<'a> SvgPositioned for Padding<'a> {
+    fn get_bbox(&self) -> BBox {
+        self.child.get_bbox().inflated(&self.margins)
+    }
+}
+
+
+/// Moves a child so that a point on its bbox lands at a given spot. For instance, a
+/// title can be put with the middle of its bottom edge just above the middle of the top
+/// edge of the thing it names.
+pub struct Anchor<'a> {
+    child: Child<'a>,
+    offset: Point,
+}
+
+impl<'a> Anchor<'a> {
+    /// Moves the item so that the item_point of its bbox is at the spot.
+    pub fn at(item: impl SvgPositioned + 'a, item_point: BoxPoint, spot: Point) -> Self {
+        let offset = match item.get_bbox().rect() {
+            None => (0.0, 0.0),
+            Some(rect) => {
+                let (x, y) = box_point(&rect, item_point);
+                (spot.0 - x, spot.1 - y)
+            },
+        };
+        Anchor{child: Box::new(item), offset}
+    }
+
+    /// Moves the item so that the item_point of its bbox is at the target_point of the
+    /// target's bbox moved by gap. Returns None if the target is empty, since then there
+    /// is nowhere to put it.
+    pub fn relative_to(
+        item: impl SvgPositioned + 'a,
+        item_point: BoxPoint,
+        target: &dyn SvgPositioned,
+        target_point: BoxPoint,
+        gap: Point,
+    ) -> Option<Self> {
+        let (x, y) = box_point(&target.get_bbox().rect()?, target_point);
+        Some(Anchor::at(item, item_point, (x + gap.0, y + gap.1)))
+    }
+}
+
+impl<'a> Renderable for Anchor<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        render_placed(tag_writer, std::slice::from_ref(&self.child), &[self.offset])
+    }
+}
+
+impl<'a> SvgPositioned for Anchor<'a> {
+    fn get_bbox(&self) -> BBox {
+        placed_bbox(std::slice::from_ref(&self.child), &[self.offset])
+    }
+}
+
+
+/// Draws the children on top of each other (the first one at the back), lined up within
+/// the room taken by the biggest of them. That room starts at (0, 0).
+pub struct Overlay<'a> {
+    children: Vec<Child<'a>>,
+    align: BoxPoint,
+}
+
+impl<'a> Overlay<'a> {
+    pub fn new(align: BoxPoint) -> Self {
+        Overlay{children: Vec::new(), align}
+    }
+
+    pub fn add(&mut self, item: impl SvgPositioned + 'a) {
+        self.children.push(Box::new(item));
+    }
+
+    fn offsets(&self) -> Vec<Point> {
+        let horizontal = line_up(&self.children, 0.0, self.align.1, horizontal_span, vertical_span);
+        let vertical = line_up(&self.children, 0.0, self.align.0, vertical_span, horizontal_span);
+        horizontal.iter().zip(vertical)
+            .map(|((_, dy), (_, dx))| (dx, *dy))
+            .collect()
+    }
+}
+
+impl<'a> Renderable for Overlay<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        render_placed(tag_writer, &self.children, &self.offsets())
+    }
+}
+
+impl<'a> SvgPositioned for Overlay<'a> {
+    fn get_bbox(&self) -> BBox {
+        placed_bbox(&self.children, &self.offsets())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Style;
+    use crate::svg_render::RoundedRect;
+
+    fn block(width: Coord, height: Coord) -> RoundedRect {
+        RoundedRect::new(Rect::new_ltwh(5.0, 5.0, width, height), 0.0, Style::filled("#000000"))
+    }
+
+    #[test]
+    fn test_stacks() {
+        let mut hstack = HStack::new(2.0, Align::End);
+        hstack.add(block(10.0, 4.0));
+        hstack.add(None::<RoundedRect>);
+        hstack.add(block(6.0, 8.0));
+        let offsets = hstack.offsets();
+        assert_eq!(offsets[0], (-5.0, -1.0)); // bottom lined up with the taller one
+        assert_eq!(offsets[2], (7.0, -5.0));
+        assert_eq!(hstack.get_bbox(), Rect::new_ltwh(0.0, 0.0, 18.0, 8.0).into());
+
+        let mut vstack = VStack::new(1.0, Align::Center);
+        vstack.add(block(10.0, 4.0));
+        vstack.add(block(6.0, 8.0));
+        assert_eq!(vstack.offsets()[1], (-3.0, 0.0));
+        assert_eq!(vstack.get_bbox(), Rect::new_ltwh(0.0, 0.0, 10.0, 13.0).into());
+    }
+
+    #[test]
+    fn test_grid_and_anchor() {
+        let mut grid = Grid::new(2, (1.0, 1.0));
+        grid.add(block(10.0, 4.0));
+        grid.add(block(6.0, 8.0));
+        grid.add(block(3.0, 3.0));
+        grid.set_cell_align((Align::End, Align::Start));
+        assert_eq!(grid.offsets()[2], (2.0, 4.0)); // right side of a 10 wide cell, below an 8 tall row
+        assert_eq!(grid.get_bbox(), Rect::new_ltwh(0.0, 0.0, 17.0, 12.0).into());
+
+        let target = block(20.0, 10.0);
+        let label = Anchor::relative_to(block(4.0, 2.0), (Align::Center, Align::End), &target, (Align::Center, Align::Start), (0.0, -1.0));
+        assert_eq!(label.unwrap().get_bbox(), Rect::new_ltwh(13.0, 2.0, 4.0, 2.0).into());
+
+        let mut overlay = Overlay::new((Align::Center, Align::End));
+        overlay.add(block(10.0, 4.0));
+        overlay.add(block(6.0, 8.0));
+        assert_eq!(overlay.offsets(), vec![(-5.0, -1.0), (-3.0, -5.0)]);
+        assert!(Anchor::relative_to(block(4.0, 2.0), (Align::Center, Align::End), &Group::new(), (Align::Center, Align::Start), (0.0, 0.0)).is_none());
+    }
+}
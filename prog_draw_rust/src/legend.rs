@@ -0,0 +1,154 @@
+//
+// A Legend is a key explaining what the colors in a drawing mean: a column of swatches,
+// each with a label beside it, and optionally a title over them. It can be built from
+// a list of entries or from a description of overlapping sets (like a Venn diagram).
+//
+
+use crate::geometry::{BBox, Coord, Rect};
+use crate::layout::{Align, HStack, VStack};
+use crate::style::Style;
+use crate::svg_render::{RoundedRect, SvgPositioned, Text, TextAnchor, VerticalAlign};
+use crate::svg_writer::{Renderable, TagWriter, TagWriterError};
+
+
+const SWATCH_CORNER_RADIUS: Coord = 2.0;
+
+
+/// One line of a Legend: a swatch painted with the style and the label explaining it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegendEntry {
+    pub swatch: Style,
+    pub label: String,
+}
+
+impl LegendEntry {
+    pub fn new(swatch: Style, label: &str) -> Self {
+        LegendEntry{swatch, label: label.to_string()}
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Legend {
+    title: Option<String>,
+    entries: Vec<LegendEntry>,
+    text_style: Style,
+    title_style: Style,
+    swatch_size: Coord,
+    spacing: Coord,
+}
+
+impl Legend {
+    /// Creates an empty Legend whose labels are drawn in the text_style. Its top left
+    /// corner is at the origin.
+    pub fn new(text_style: Style) -> Self {
+        let swatch_size = text_style.effective_font_size();
+        Legend{
+            title: None,
+            entries: Vec::new(),
+            title_style: text_style.clone().with_font_weight("bold"),
+            text_style,
+            swatch_size,
+            spacing: swatch_size / 2.0,
+        }
+    }
+
+    /// Creates a Legend with an entry for every combination of membership in the named
+    /// sets. The style_for function is passed one bool per set (true if the combination is
+    /// in it). Entries go in order with the first set counting most, so with 3 sets the
+    /// entry at index 4 is in just the first set. Each label lists the sets it is in,
+    /// except "None" and "All".
+    pub fn from_sets(text_style: Style, set_names: &[&str], style_for: impl Fn(&[bool]) -> Style) -> Self {
+        let mut legend = Legend::new(text_style);
+        let count = set_names.len();
+        for index in 0..(1usize << count) {
+            let members: Vec<bool> = (0..count).map(|i| index & (1 << (count - 1 - i)) != 0).collect();
+            let label = if members.iter().all(|x| !x) {
+                "None".to_string()
+            } else if members.iter().all(|x| *x) {
+                "All".to_string()
+            } else {
+                set_names.iter().zip(members.iter())
+                    .filter(|(_, member)| **member)
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(" + ")
+            };
+            legend.add_entry(style_for(&members), &label);
+        }
+        legend
+    }
+
+    pub fn set_title(&mut self, title: Option<&str>) {
+        self.title = title.map(|x| x.to_string());
+    }
+
+    pub fn add_entry(&mut self, swatch: Style, label: &str) {
+        self.entries.push(LegendEntry::new(swatch, label));
+    }
+
+    pub fn entries(&self) -> &Vec<LegendEntry> {
+        &self.entries
+    }
+
+    /// Call this to set the width and height of each swatch (it defaults to the font size).
+    pub fn set_swatch_size(&mut self, swatch_size: Coord) {
+        self.swatch_size = swatch_size;
+    }
+
+    /// Call this to set the space between lines and between each swatch and its label.
+    pub fn set_spacing(&mut self, spacing: Coord) {
+        self.spacing = spacing;
+    }
+
+    /// Returns the legend laid out, with the text measured.
+    fn layout(&self) -> VStack<'static> {
+        let mut column = VStack::new(self.spacing, Align::Start);
+        if let Some(title) = &self.title {
+            column.add(self.text(title, &self.title_style));
+        }
+        for entry in self.entries.iter() {
+            let mut line = HStack::new(self.spacing, Align::Center);
+            let swatch_rect = Rect::new_ltwh(0.0, 0.0, self.swatch_size, self.swatch_size);
+            line.add(RoundedRect::new(swatch_rect, SWATCH_CORNER_RADIUS, entry.swatch.clone()));
+            line.add(self.text(&entry.label, &self.text_style));
+            column.add(line);
+        }
+        column
+    }
+
+    fn text(&self, text: &str, style: &Style) -> Text {
+        let mut text = Text::with_style(text, (0.0, 0.0), style.clone());
+        text.set_alignment(TextAnchor::Start, VerticalAlign::Top);
+        text
+    }
+}
+
+impl Renderable for Legend {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        self.layout().render(tag_writer)
+    }
+}
+
+impl SvgPositioned for Legend {
+    fn get_bbox(&self) -> BBox {
+        self.layout().get_bbox()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_sets() {
+        let legend = Legend::from_sets(Style::new(), &["A", "B"], |members| {
+            Style::filled(if members[0] {"#FF0000"} else {"#FFFFFF"})
+        });
+        let labels: Vec<&str> = legend.entries().iter().map(|x| x.label.as_str()).collect();
+        assert_eq!(labels, ["None", "B", "A", "All"]);
+        assert_eq!(legend.entries()[2].swatch, Style::filled("#FF0000"));
+        assert_eq!(legend.entries()[1].swatch, Style::filled("#FFFFFF"));
+    }
+}
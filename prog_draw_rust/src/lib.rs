@@ -1,8 +1,16 @@
 pub mod geometry;
+pub mod units;
 pub mod data_tree;
 pub mod tidy_tree;
 pub mod svg_writer;
 pub mod svg_render;
+pub mod style;
+pub mod layout;
+pub mod layers;
+pub mod legend;
+pub mod marker;
+pub mod diagnostics;
+pub mod hit_test;
 pub mod text_size;
 pub mod macos_text_size;
 
@@ -68,6 +68,7 @@ mod test {
 
 
     #[test]
+    #[cfg_attr(not(target_os = "macos"), ignore = "reads the Arial font that comes with MacOS")]
     fn invoke_text_size() {
         let text = "Hello, World";
         let font_name = "Arial";
@@ -0,0 +1,164 @@
+//
+// Markers are the little shapes (like arrowheads) drawn at the ends of a path, turned to
+// follow the direction it's heading. Each different marker is defined only once in an
+// SVG: while rendering, the markers that get used are collected here, and the Svg writes
+// out a <marker> definition for each of them at the end. So markers only show up when
+// they are rendered inside an Svg. The id of each definition comes from what the marker
+// looks like, so several SVGs inlined in one page can't refer to each other's markers by
+// mistake.
+//
+
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use crate::geometry::{BBox, Coord, Margins, Point, Rect};
+use crate::style::Style;
+use crate::svg_render::{Circle, Polygon};
+use crate::svg_writer::{Attributes, Renderable, TagWriter, TagWriterError};
+
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MarkerShape {
+    /// A triangle whose tip is on the end of the path.
+    Arrow,
+    /// A circle centered on the end of the path.
+    Dot,
+    /// A diamond centered on the end of the path.
+    Diamond,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    shape: MarkerShape,
+    /// How long (and how wide) the shape is.
+    size: Coord,
+    style: Style,
+}
+
+
+thread_local!{
+    /// This threadlocal variable holds every different marker used since the Svg being
+    /// rendered began.
+    static MARKERS_IN_USE: RefCell<Vec<Marker>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns the id that refers to the marker's definition, adding it to the markers that
+/// need to be defined if it's not already there. The definition is only written out by an
+/// Svg, so outside of one the id refers to nothing.
+pub fn marker_id(marker: &Marker) -> String {
+    MARKERS_IN_USE.with(|it| {
+        let mut markers = it.borrow_mut();
+        if !markers.contains(marker) {
+            markers.push(marker.clone());
+        }
+    });
+    marker.content_id()
+}
+
+/// Writes a <defs> with the definition of every marker used since this was last called
+/// (if there were any), then forgets them.
+pub fn render_marker_defs(tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+    let markers = MARKERS_IN_USE.with(|it| it.take());
+    if !markers.is_empty() {
+        tag_writer.begin_tag("defs", Attributes::new())?;
+        for marker in markers.iter() {
+            marker.render_def(&marker.content_id(), tag_writer)?;
+        }
+        tag_writer.end_tag("defs")?;
+    }
+    Ok(())
+}
+
+/// Forgets any markers that were used, without defining them.
+pub fn clear_markers_in_use() {
+    MARKERS_IN_USE.with(|it| it.borrow_mut().clear());
+}
+
+
+impl Marker {
+    pub fn new(shape: MarkerShape, size: Coord, style: Style) -> Self {
+        Marker{shape, size, style}
+    }
+
+    /// Returns an id made from the shape, size, and style, so two markers get the same id
+    /// exactly when they look the same.
+    fn content_id(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", self).hash(&mut hasher);
+        format!("marker-{:016x}", hasher.finish())
+    }
+
+    /// Returns the corners of the shape, with the end of the path at (0,0) and the path
+    /// heading in the +x direction. (For a Dot it's the corners of a box around it.)
+    fn outline(&self) -> Vec<Point> {
+        let s = self.size;
+        match self.shape {
+            MarkerShape::Arrow => vec![(0.0, 0.0), (-s, -s / 2.0), (-s, s / 2.0)],
+            MarkerShape::Dot => vec![(-s / 2.0, -s / 2.0), (s / 2.0, -s / 2.0), (s / 2.0, s / 2.0), (-s / 2.0, s / 2.0)],
+            MarkerShape::Diamond => vec![(-s / 2.0, 0.0), (0.0, -s / 2.0), (s / 2.0, 0.0), (0.0, s / 2.0)],
+        }
+    }
+
+    /// Returns the area the marker covers when drawn at the point, facing in the direction
+    /// (a vector of length 1).
+    pub fn bbox_at(&self, point: Point, direction: Point) -> BBox {
+        let (dx, dy) = direction;
+        self.outline().iter()
+            .map(|(x, y)| (point.0 + x * dx - y * dy, point.1 + x * dy + y * dx))
+            .map(|p| Rect::new_ltwh(p.0, p.1, 0.0, 0.0).into())
+            .collect::<BBox>()
+            .inflated(&Margins::uniform(self.style.stroke_overhang()))
+    }
+
+    /// Writes the <marker> definition. It is drawn at the size given (whatever the
+    /// stroke width is), and is reversed at the start of a path so arrows point outward.
+    fn render_def(&self, id: &str, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        let s = self.size;
+        tag_writer.begin_tag("marker", Attributes::from([
+            ("id", id.to_string()),
+            ("viewBox", format!("{} {} {} {}", -s, -s, 2.0 * s, 2.0 * s)),
+            ("markerWidth", (2.0 * s).to_string()),
+            ("markerHeight", (2.0 * s).to_string()),
+            ("refX", "0".to_string()),
+            ("refY", "0".to_string()),
+            ("markerUnits", "userSpaceOnUse".to_string()),
+            ("orient", "auto-start-reverse".to_string()),
+            ("overflow", "visible".to_string()),
+        ]))?;
+        match self.shape {
+            MarkerShape::Dot => Circle::new((0.0, 0.0), s / 2.0, self.style.clone()).render(tag_writer)?,
+            _ => Polygon::new(self.outline(), self.style.clone()).render(tag_writer)?,
+        }
+        tag_writer.end_tag("marker")?;
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_bbox_and_ids() {
+        let arrow = Marker::new(MarkerShape::Arrow, 4.0, Style::filled("#000000"));
+        assert_eq!(arrow.bbox_at((10.0, 0.0), (1.0, 0.0)), Rect::new_ltrb(6.0, -2.0, 10.0, 2.0).into());
+        assert_eq!(arrow.bbox_at((0.0, 10.0), (0.0, -1.0)), Rect::new_ltrb(-2.0, 10.0, 2.0, 14.0).into());
+        let dot = Marker::new(MarkerShape::Dot, 4.0, Style::filled("#000000").with_stroke("#000000", 2.0));
+        assert_eq!(dot.bbox_at((0.0, 0.0), (1.0, 0.0)), Rect::new_ltrb(-3.0, -3.0, 3.0, 3.0).into());
+
+        clear_markers_in_use();
+        let arrow_id = marker_id(&arrow);
+        assert_eq!(marker_id(&arrow.clone()), arrow_id);
+        assert_ne!(marker_id(&dot), arrow_id);
+        let red_arrow = Marker::new(MarkerShape::Arrow, 4.0, Style::filled("#FF0000"));
+        assert_ne!(marker_id(&red_arrow), arrow_id);
+        MARKERS_IN_USE.with(|it| assert_eq!(it.borrow().len(), 3));
+
+        // --- the id doesn't depend on what else was used first ---
+        clear_markers_in_use();
+        assert_eq!(marker_id(&dot), marker_id(&dot.clone()));
+        assert_eq!(marker_id(&arrow), arrow_id);
+        clear_markers_in_use();
+    }
+}
@@ -0,0 +1,214 @@
+//
+// A Style says how something gets painted: its fill and outline, and (for text) its font
+// and color. Every field is optional; a field that isn't set falls back to the same field
+// of a parent style (see cascade()) and finally to the defaults in Style::base().
+//
+
+use crate::geometry::{BBox, Coord, Margins, Rect};
+use crate::svg_writer::Attributes;
+
+
+const DEFAULT_FILL: &str = "none";
+const DEFAULT_STROKE: &str = "#000000";
+const DEFAULT_STROKE_WIDTH: Coord = 1.0;
+const DEFAULT_FONT: &str = "Arial";
+pub(crate) const DEFAULT_FONT_SIZE: Coord = 12.0;
+const DEFAULT_FONT_WEIGHT: &str = "normal";
+const DEFAULT_TEXT_COLOR: &str = "#000000";
+
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    /// The color inside a shape, or "none".
+    pub fill: Option<String>,
+    /// The color of the outline of a shape, or "none".
+    pub stroke: Option<String>,
+    pub stroke_width: Option<Coord>,
+    /// From 0.0 (invisible) to 1.0 (solid).
+    pub opacity: Option<Coord>,
+    /// Lengths of alternating dashes and gaps. Empty means a solid line.
+    pub dash_array: Option<Vec<Coord>>,
+    pub font_family: Option<String>,
+    /// The font size, in pixels.
+    pub font_size: Option<Coord>,
+    /// A CSS font weight, like "bold" or "400".
+    pub font_weight: Option<String>,
+    pub text_color: Option<String>,
+}
+
+impl Style {
+    /// Returns a Style with nothing set, so everything comes from the parent.
+    pub fn new() -> Self {
+        Style::default()
+    }
+
+    /// Returns the Style with everything set to the defaults.
+    pub fn base() -> Self {
+        Style{
+            fill: Some(DEFAULT_FILL.to_string()),
+            stroke: Some(DEFAULT_STROKE.to_string()),
+            stroke_width: Some(DEFAULT_STROKE_WIDTH),
+            opacity: Some(1.0),
+            dash_array: Some(Vec::new()),
+            font_family: Some(DEFAULT_FONT.to_string()),
+            font_size: Some(DEFAULT_FONT_SIZE),
+            font_weight: Some(DEFAULT_FONT_WEIGHT.to_string()),
+            text_color: Some(DEFAULT_TEXT_COLOR.to_string()),
+        }
+    }
+
+    /// Returns a Style that fills with the color and has no outline.
+    pub fn filled(color: &str) -> Self {
+        Style::new().with_fill(color).without_stroke()
+    }
+
+    pub fn with_fill(mut self, color: &str) -> Self {
+        self.fill = Some(color.to_string());
+        self
+    }
+
+    /// Returns this, but outlined with the color and width.
+    pub fn with_stroke(mut self, color: &str, width: Coord) -> Self {
+        self.stroke = Some(color.to_string());
+        self.stroke_width = Some(width);
+        self
+    }
+
+    pub fn without_stroke(mut self) -> Self {
+        self.stroke = Some("none".to_string());
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: Coord) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    pub fn with_dash_array(mut self, dash_array: &[Coord]) -> Self {
+        self.dash_array = Some(dash_array.to_vec());
+        self
+    }
+
+    pub fn with_font_family(mut self, font_family: &str) -> Self {
+        self.font_family = Some(font_family.to_string());
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: Coord) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    pub fn with_font_weight(mut self, font_weight: &str) -> Self {
+        self.font_weight = Some(font_weight.to_string());
+        self
+    }
+
+    pub fn with_text_color(mut self, color: &str) -> Self {
+        self.text_color = Some(color.to_string());
+        self
+    }
+
+    /// Returns this style with anything that isn't set taken from the parent.
+    pub fn cascade(&self, parent: &Style) -> Style {
+        Style{
+            fill: self.fill.clone().or_else(|| parent.fill.clone()),
+            stroke: self.stroke.clone().or_else(|| parent.stroke.clone()),
+            stroke_width: self.stroke_width.or(parent.stroke_width),
+            opacity: self.opacity.or(parent.opacity),
+            dash_array: self.dash_array.clone().or_else(|| parent.dash_array.clone()),
+            font_family: self.font_family.clone().or_else(|| parent.font_family.clone()),
+            font_size: self.font_size.or(parent.font_size),
+            font_weight: self.font_weight.clone().or_else(|| parent.font_weight.clone()),
+            text_color: self.text_color.clone().or_else(|| parent.text_color.clone()),
+        }
+    }
+
+    /// Returns this style with anything that isn't set taken from the defaults.
+    pub fn resolved(&self) -> Style {
+        self.cascade(&Style::base())
+    }
+
+    /// Returns the font family that will be used.
+    pub fn effective_font_family(&self) -> &str {
+        self.font_family.as_deref().unwrap_or(DEFAULT_FONT)
+    }
+
+    /// Returns the font size that will be used.
+    pub fn effective_font_size(&self) -> Coord {
+        self.font_size.unwrap_or(DEFAULT_FONT_SIZE)
+    }
+
+    /// Returns how far the outline sticks out past the edge of a shape.
+    pub fn stroke_overhang(&self) -> Coord {
+        match self.stroke.as_deref().unwrap_or(DEFAULT_STROKE) {
+            "none" => 0.0,
+            _ => self.stroke_width.unwrap_or(DEFAULT_STROKE_WIDTH) / 2.0,
+        }
+    }
+
+    /// Returns the rect grown by the part of the outline that sticks out past it.
+    pub fn stroked(&self, rect: Rect) -> BBox {
+        rect.inflated(&Margins::uniform(self.stroke_overhang())).into()
+    }
+
+    /// Returns the attributes with those for painting a shape added.
+    pub fn add_to(&self, attributes: Attributes) -> Attributes {
+        let style = self.resolved();
+        let stroke = style.stroke.unwrap();
+        let mut attributes = attributes
+            .with_field("fill", style.fill.unwrap());
+        if stroke != "none" {
+            attributes = attributes
+                .with_field("stroke", stroke)
+                .with_field("stroke-width", style.stroke_width.unwrap());
+            let dash_array = style.dash_array.unwrap();
+            if !dash_array.is_empty() {
+                let dashes: Vec<String> = dash_array.iter().map(|x| x.to_string()).collect();
+                attributes = attributes.with_field("stroke-dasharray", dashes.join(" "));
+            }
+        }
+        add_opacity(attributes, self.opacity)
+    }
+
+    /// Returns the attributes with those for drawing text added.
+    pub fn add_text_to(&self, attributes: Attributes) -> Attributes {
+        let style = self.resolved();
+        let mut attributes = attributes
+            .with_field("fill", style.text_color.unwrap())
+            .with_field("font-family", style.font_family.unwrap())
+            .with_field("font-size", style.font_size.unwrap());
+        if let Some(font_weight) = &self.font_weight {
+            attributes = attributes.with_field("font-weight", font_weight);
+        }
+        add_opacity(attributes, self.opacity)
+    }
+}
+
+
+/// Returns the attributes with the opacity added (if there is any).
+fn add_opacity(attributes: Attributes, opacity: Option<Coord>) -> Attributes {
+    match opacity {
+        Some(opacity) if opacity != 1.0 => attributes.with_field("opacity", opacity),
+        _ => attributes,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cascade() {
+        let parent = Style::new().with_stroke("#FF0000", 3.0).with_font_size(20.0);
+        let child = Style::filled("#00FF00").cascade(&parent);
+        assert_eq!(child.fill.as_deref(), Some("#00FF00"));
+        assert_eq!(child.stroke.as_deref(), Some("none"));
+        assert_eq!(child.stroke_width, Some(3.0));
+        assert_eq!(child.effective_font_size(), 20.0);
+        assert_eq!(child.effective_font_family(), "Arial");
+        assert_eq!(child.stroke_overhang(), 0.0);
+        assert_eq!(parent.stroke_overhang(), 1.5);
+    }
+}
@@ -1,94 +1,168 @@
 use crate::svg_writer::{Renderable, TagWriter, TagWriterError, Attributes};
-use crate::geometry::{Coord, Point, Rect};
+use crate::geometry::{BBox, Coord, Margins, Path, Point, Rect, Transform};
+use crate::layout::Align;
+use crate::marker::{clear_markers_in_use, marker_id, render_marker_defs, Marker};
+use crate::style::{Style, DEFAULT_FONT_SIZE};
 use crate::text_size::get_system_text_sizer;
+use crate::units::{Length, PageSize, Unit};
 
 
 /// A trait for anything whose SVG dimensions can be measured and used to lay it
 /// out.
 pub trait SvgPositioned: Renderable {
     /// Returns a bounding box for this item. The bounding box is relative to the local
-    /// coordinate system.
-    /// FIXME: I keep encountering things that sometimes take up NO space. Maybe
-    ///   I need to make this return Option<Rect> to handle that case. If so, then
-    ///   I also need to make reduce() with Rect::cover() continue to work somehow.
-    fn get_bbox(&self) -> Rect;
+    /// coordinate system. Things that take up no space return BBox::Empty.
+    fn get_bbox(&self) -> BBox;
+}
+
+impl<T: SvgPositioned + ?Sized> SvgPositioned for &T {
+    fn get_bbox(&self) -> BBox {
+        (**self).get_bbox()
+    }
+}
+
+impl<T: SvgPositioned + ?Sized> SvgPositioned for Box<T> {
+    fn get_bbox(&self) -> BBox {
+        self.as_ref().get_bbox()
+    }
+}
+
+impl<T: SvgPositioned> SvgPositioned for Option<T> {
+    fn get_bbox(&self) -> BBox {
+        match self {
+            None => BBox::Empty,
+            Some(item) => item.get_bbox(),
+        }
+    }
+}
+
+impl<T: SvgPositioned> SvgPositioned for Vec<T> {
+    fn get_bbox(&self) -> BBox {
+        self.iter().map(|item| item.get_bbox()).collect()
+    }
 }
 
 
 
 pub struct BasicBox {
-    x: Coord,
-    y: Coord,
-    height: Coord,
-    width: Coord,
+    rect: Rect,
+    style: Style,
+}
+
+impl BasicBox {
+    /// Create a BasicBox. Anything the style doesn't set comes from a 3 pixel black outline
+    /// with no fill.
+    pub fn new(rect: Rect, style: Style) -> Self {
+        let style = style.cascade(&Style::new().with_stroke("black", 3.0));
+        BasicBox{rect, style}
+    }
 }
 
 impl Renderable for BasicBox {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
-        tag_writer.single_tag("rect", Attributes::from([
-            ("x", self.x.to_string()),
-            ("y", self.y.to_string()),
-            ("height", self.height.to_string()),
-            ("width", self.width.to_string()),
-            ("fill", "none".to_string()),
-            ("stroke", "black".to_string()),
-            ("stroke-width", 3.to_string()),
-        ]))
+        tag_writer.single_tag("rect", self.style.add_to(Attributes::from([
+            ("x", self.rect.left()),
+            ("y", self.rect.top()),
+            ("height", self.rect.height()),
+            ("width", self.rect.width()),
+        ])))
     }
 }
 
 impl SvgPositioned for BasicBox {
-    fn get_bbox(&self) -> Rect {
-        Rect::new_ltwh(self.x, self.y, self.width, self.height)
+    fn get_bbox(&self) -> BBox {
+        self.style.stroked(self.rect)
     }
 }
 
 
+/// Which part of a Text goes at its position horizontally.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TextAnchor {
+    Start,
+    #[default]
+    Middle,
+    End,
+}
+
+/// Which part of a Text goes at its position vertically.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+    Top,
+    #[default]
+    Middle,
+    /// The line the letters sit on (descenders hang below it).
+    Baseline,
+    Bottom,
+}
+
+/// How much of the height of a line of text hangs below the baseline (this is about
+/// right for Arial and most other fonts).
+const DESCENT_FRACTION: Coord = 0.21;
+
+
+/// Converts a CSS font size to px, or returns None if it isn't one we understand.
+fn css_font_size(css: &str) -> Option<Coord> {
+    let css = css.trim();
+    for (suffix, scale) in [("rem", 1.0), ("em", 1.0), ("%", 0.01)] {
+        if let Some(number) = css.strip_suffix(suffix) {
+            return number.trim().parse::<Coord>().ok().map(|x| x * scale * DEFAULT_FONT_SIZE);
+        }
+    }
+    Length::parse(css).map(|length| length.to_px())
+}
+
+
 pub struct Text {
     text: String,
-    position: Point, // The center of the text
+    position: Point, // Where the text is anchored; by default its center
     text_size_cached: Point,
-    font_family: Option<String>,
-    font_size: Option<String>,
+    style: Style,
+    anchor: TextAnchor,
+    vertical_align: VerticalAlign,
 }
 
-const DEFAULT_FONT: &str = "Arial";
-const DEFAULT_SIZE: f32 = 12.0;
-const DEFAULT_COLOR: &str = "#000000";
-
 impl Text {
     /// Construct a new Text, providing the text and the position.
     pub fn new(text: &str, position: Point) -> Self {
-        let mut answer = Text{text: text.to_string(), position, text_size_cached: (0.0, 0.0), font_family: None, font_size: None};
-        answer.cache_text_size();
-        answer
+        Text::with_style(text, position, Style::new())
     }
 
     /// Construct a new Text, providing the text, position, and styling. font_family and font_size are
-    /// css strings for their corresponding CSS fields.
+    /// css strings for their corresponding CSS fields. font_size can be in any Unit, or in em, rem,
+    /// or % of the default font size; this panics if it is anything else.
     pub fn new_styled(text: &str, position: Point, font_family: Option<String>, font_size: Option<String>) -> Self {
-        let mut answer = Text{text: text.to_string(), position, text_size_cached: (0.0, 0.0), font_family, font_size};
+        let font_size = font_size.map(|css| css_font_size(&css)
+            .unwrap_or_else(|| panic!("Font size '{}' is not one that can be measured.", css)));
+        let style = Style{font_family, font_size, ..Style::new()};
+        Text::with_style(text, position, style)
+    }
+
+    /// Construct a new Text, providing the text, position, and the style (only the font and
+    /// text parts of the style are used).
+    pub fn with_style(text: &str, position: Point, style: Style) -> Self {
+        let mut answer = Text{
+            text: text.to_string(),
+            position,
+            text_size_cached: (0.0, 0.0),
+            style,
+            anchor: TextAnchor::default(),
+            vertical_align: VerticalAlign::default(),
+        };
         answer.cache_text_size();
         answer
     }
 
+    /// Call this to choose which part of the text goes at its position.
+    pub fn set_alignment(&mut self, anchor: TextAnchor, vertical_align: VerticalAlign) {
+        self.anchor = anchor;
+        self.vertical_align = vertical_align;
+    }
+
     /// Internal function to find the value we will store in text_size_cached.
     fn cache_text_size(&mut self) {
-        let font = match &self.font_family {
-            None => DEFAULT_FONT,
-            Some(s) => s.as_str(),
-        };
-        let size: f32 = match &self.font_size {
-            None => DEFAULT_SIZE,
-            Some(s) if s.ends_with("px") => {
-                let num_part = &s[..s.len() - 2];
-                match num_part.parse::<f32>() {
-                    Err(_) => DEFAULT_SIZE,
-                    Ok(size) => size,
-                }
-            },
-            Some(_) => DEFAULT_SIZE,
-        };
+        let font = self.style.effective_font_family();
+        let size = self.style.effective_font_size() as f32;
         self.text_size_cached = match get_system_text_sizer().text_size(&self.text, font, size) {
             Err(_) => panic!("Sizing isn't working."),
             Ok((width,height)) => (width as Coord, height as Coord)
@@ -98,36 +172,381 @@ impl Text {
 
 
 impl SvgPositioned for Text {
-    fn get_bbox(&self) -> Rect {
-        Rect::new_cwh(self.position, self.text_size_cached.0, self.text_size_cached.1)
+    fn get_bbox(&self) -> BBox {
+        let (x, y) = self.position;
+        let (width, height) = self.text_size_cached;
+        let left = match self.anchor {
+            TextAnchor::Start => x,
+            TextAnchor::Middle => x - width / 2.0,
+            TextAnchor::End => x - width,
+        };
+        let top = match self.vertical_align {
+            VerticalAlign::Top => y,
+            VerticalAlign::Middle => y - height / 2.0,
+            VerticalAlign::Baseline => y - height * (1.0 - DESCENT_FRACTION),
+            VerticalAlign::Bottom => y - height,
+        };
+        Rect::new_ltwh(left, top, width, height).into()
     }
 }
 
 impl Renderable for Text {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        let text_anchor = match self.anchor {
+            TextAnchor::Start => "start",
+            TextAnchor::Middle => "middle",
+            TextAnchor::End => "end",
+        };
         let mut attributes = Attributes::from([
             ("x", self.position.0.to_string().as_str()),
             ("y", self.position.1.to_string().as_str()),
-            ("fill", DEFAULT_COLOR),
-            ("text-anchor", "middle"),
-            ("dominant-baseline", "central"),
+            ("text-anchor", text_anchor),
             // FIXME: Now that I've figured out how to align text properly, maybe I should use that in OTHER places.
         ]);
-        if self.font_family.is_some() {
-            attributes = attributes.with_field("font-family", self.font_family.as_ref().unwrap());
+        let dominant_baseline = match self.vertical_align {
+            VerticalAlign::Top => Some("text-before-edge"),
+            VerticalAlign::Middle => Some("central"),
+            VerticalAlign::Baseline => None, // the SVG default
+            VerticalAlign::Bottom => Some("text-after-edge"),
+        };
+        if let Some(dominant_baseline) = dominant_baseline {
+            attributes = attributes.with_field("dominant-baseline", dominant_baseline);
+        }
+        tag_writer.tag_with_text("text", self.style.add_text_to(attributes), &self.text)
+    }
+}
+
+
+/// Returns the points as the value of a "points" attribute.
+fn points_str(points: &[Point]) -> String {
+    points.iter()
+        .map(|p| format!("{},{}", p.0, p.1))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Returns the smallest Rect that covers all the points, or None if there aren't any.
+fn points_rect(points: &[Point]) -> Option<Rect> {
+    points.iter()
+        .map(|p| BBox::from(Rect::new_ltwh(p.0, p.1, 0.0, 0.0)))
+        .collect::<BBox>()
+        .rect()
+}
+
+
+pub struct Circle {
+    center: Point,
+    radius: Coord,
+    style: Style,
+}
+
+impl Circle {
+    pub fn new(center: Point, radius: Coord, style: Style) -> Self {
+        Circle{center, radius, style}
+    }
+}
+
+impl Renderable for Circle {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        tag_writer.single_tag("circle", self.style.add_to(Attributes::from([
+            ("cx", self.center.0),
+            ("cy", self.center.1),
+            ("r", self.radius),
+        ])))
+    }
+}
+
+impl SvgPositioned for Circle {
+    fn get_bbox(&self) -> BBox {
+        self.style.stroked(Rect::new_cwh(self.center, 2.0 * self.radius, 2.0 * self.radius))
+    }
+}
+
+
+pub struct Ellipse {
+    center: Point,
+    radii: (Coord, Coord),
+    style: Style,
+}
+
+impl Ellipse {
+    pub fn new(center: Point, radii: (Coord, Coord), style: Style) -> Self {
+        Ellipse{center, radii, style}
+    }
+}
+
+impl Renderable for Ellipse {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        tag_writer.single_tag("ellipse", self.style.add_to(Attributes::from([
+            ("cx", self.center.0),
+            ("cy", self.center.1),
+            ("rx", self.radii.0),
+            ("ry", self.radii.1),
+        ])))
+    }
+}
+
+impl SvgPositioned for Ellipse {
+    fn get_bbox(&self) -> BBox {
+        self.style.stroked(Rect::new_cwh(self.center, 2.0 * self.radii.0, 2.0 * self.radii.1))
+    }
+}
+
+
+pub struct RoundedRect {
+    rect: Rect,
+    corner_radius: Coord,
+    style: Style,
+}
+
+impl RoundedRect {
+    pub fn new(rect: Rect, corner_radius: Coord, style: Style) -> Self {
+        RoundedRect{rect, corner_radius, style}
+    }
+}
+
+impl Renderable for RoundedRect {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        tag_writer.single_tag("rect", self.style.add_to(Attributes::from([
+            ("x", self.rect.left()),
+            ("y", self.rect.top()),
+            ("width", self.rect.width()),
+            ("height", self.rect.height()),
+            ("rx", self.corner_radius),
+        ])))
+    }
+}
+
+impl SvgPositioned for RoundedRect {
+    fn get_bbox(&self) -> BBox {
+        self.style.stroked(self.rect)
+    }
+}
+
+
+/// A straight line. The ends are cut off square, right at the end points.
+pub struct LineSegment {
+    start: Point,
+    end: Point,
+    style: Style,
+}
+
+impl LineSegment {
+    pub fn new(start: Point, end: Point, style: Style) -> Self {
+        LineSegment{start, end, style}
+    }
+}
+
+impl Renderable for LineSegment {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        tag_writer.single_tag("line", self.style.add_to(Attributes::from([
+            ("x1", self.start.0),
+            ("y1", self.start.1),
+            ("x2", self.end.0),
+            ("y2", self.end.1),
+        ])))
+    }
+}
+
+impl SvgPositioned for LineSegment {
+    fn get_bbox(&self) -> BBox {
+        // --- the stroke reaches out sideways from the line (but not past the ends) ---
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let len = dx.hypot(dy);
+        let overhang = self.style.stroke_overhang();
+        let (nx, ny) = if len == 0.0 {(0.0, 0.0)} else {(-dy / len * overhang, dx / len * overhang)};
+        let corners = [
+            (self.start.0 + nx, self.start.1 + ny),
+            (self.start.0 - nx, self.start.1 - ny),
+            (self.end.0 + nx, self.end.1 + ny),
+            (self.end.0 - nx, self.end.1 - ny),
+        ];
+        points_rect(&corners).into_iter().map(BBox::from).collect()
+    }
+}
+
+
+/// A series of connected straight lines. Corners are drawn rounded, so the outline never
+/// sticks out more than half its width past the points.
+pub struct Polyline {
+    points: Vec<Point>,
+    style: Style,
+}
+
+impl Polyline {
+    pub fn new(points: Vec<Point>, style: Style) -> Self {
+        Polyline{points, style}
+    }
+}
+
+impl Renderable for Polyline {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        tag_writer.single_tag("polyline", self.style.add_to(Attributes::from([
+            ("points", points_str(&self.points)),
+            ("stroke-linejoin", "round".to_string()),
+        ])))
+    }
+}
+
+impl SvgPositioned for Polyline {
+    fn get_bbox(&self) -> BBox {
+        match points_rect(&self.points) {
+            None => BBox::Empty,
+            Some(rect) => self.style.stroked(rect),
+        }
+    }
+}
+
+
+/// A closed shape with straight sides. Corners are drawn rounded, so the outline never
+/// sticks out more than half its width past the points.
+pub struct Polygon {
+    points: Vec<Point>,
+    style: Style,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<Point>, style: Style) -> Self {
+        Polygon{points, style}
+    }
+}
+
+impl Renderable for Polygon {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        tag_writer.single_tag("polygon", self.style.add_to(Attributes::from([
+            ("points", points_str(&self.points)),
+            ("stroke-linejoin", "round".to_string()),
+        ])))
+    }
+}
+
+impl SvgPositioned for Polygon {
+    fn get_bbox(&self) -> BBox {
+        match points_rect(&self.points) {
+            None => BBox::Empty,
+            Some(rect) => self.style.stroked(rect),
+        }
+    }
+}
+
+
+/// Draws a Path. Corners are drawn rounded, so the outline never sticks out more than
+/// half its width past the path.
+pub struct PathShape {
+    path: Path,
+    style: Style,
+    start_marker: Option<Marker>,
+    end_marker: Option<Marker>,
+}
+
+impl PathShape {
+    pub fn new(path: Path, style: Style) -> Self {
+        PathShape{path, style, start_marker: None, end_marker: None}
+    }
+
+    /// Returns this, but with markers (like arrowheads) drawn at the start and/or end. The
+    /// markers are defined by the Svg the path is rendered in, so they only appear there.
+    pub fn with_markers(mut self, start_marker: Option<Marker>, end_marker: Option<Marker>) -> Self {
+        self.start_marker = start_marker;
+        self.end_marker = end_marker;
+        self
+    }
+}
+
+impl Renderable for PathShape {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        let mut attributes = self.style.add_to(Attributes::from([
+            ("d", self.path.svg_d()),
+            ("stroke-linejoin", "round".to_string()),
+        ]));
+        if let Some(marker) = &self.start_marker {
+            attributes = attributes.with_field("marker-start", format!("url(#{})", marker_id(marker)));
+        }
+        if let Some(marker) = &self.end_marker {
+            attributes = attributes.with_field("marker-end", format!("url(#{})", marker_id(marker)));
+        }
+        tag_writer.single_tag("path", attributes)
+    }
+}
+
+impl SvgPositioned for PathShape {
+    fn get_bbox(&self) -> BBox {
+        let mut bbox = self.style.stroked(self.path.bbox());
+        if let Some(marker) = &self.start_marker {
+            // --- start markers are turned around to face away from the path ---
+            let (dx, dy) = self.path.tangent_at(0.0);
+            bbox = bbox.cover(&marker.bbox_at(self.path.start(), (-dx, -dy)));
         }
-        if self.font_size.is_some() {
-            attributes = attributes.with_field("style", format!("font-size: {}", self.font_size.as_ref().unwrap()));
+        if let Some(marker) = &self.end_marker {
+            bbox = bbox.cover(&marker.bbox_at(self.path.end(), self.path.tangent_at(1.0)));
         }
-        tag_writer.tag_with_text("text", attributes, &self.text)
+        bbox
     }
 }
 
 
+/// A picture, stretched to fill the rect. The picture is stored inside the SVG as a
+/// data URI, so the SVG doesn't depend on any other files.
+pub struct Image {
+    rect: Rect,
+    data_uri: String,
+}
+
+impl Image {
+    /// Create an Image from a data URI (like "data:image/png;base64,...").
+    pub fn new(rect: Rect, data_uri: &str) -> Self {
+        Image{rect, data_uri: data_uri.to_string()}
+    }
+
+    /// Create an Image from the contents of an image file and its mime type (like
+    /// "image/png").
+    pub fn from_bytes(rect: Rect, mime_type: &str, bytes: &[u8]) -> Self {
+        Image{rect, data_uri: format!("data:{};base64,{}", mime_type, base64_encode(bytes))}
+    }
+}
+
+impl Renderable for Image {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        tag_writer.single_tag("image", Attributes::from([
+            ("x", self.rect.left().to_string()),
+            ("y", self.rect.top().to_string()),
+            ("width", self.rect.width().to_string()),
+            ("height", self.rect.height().to_string()),
+            ("href", self.data_uri.clone()),
+        ]))
+    }
+}
+
+impl SvgPositioned for Image {
+    fn get_bbox(&self) -> BBox {
+        self.rect.into()
+    }
+}
+
+
+/// Returns the bytes encoded as base64 (the standard alphabet, with padding).
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut answer = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                answer.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                answer.push('=');
+            }
+        }
+    }
+    answer
+}
+
+
+
 pub struct Group<'a> {
     pub items: Vec<&'a dyn SvgPositioned>,
-    translate: Option<(Coord, Coord)>,
-    scale: Option<Coord>,
+    transform: Transform,
 }
 
 impl<'a> Group<'a> {
@@ -135,45 +554,39 @@ impl<'a> Group<'a> {
 
 
     pub fn new() -> Self {
-        Group{items: Vec::new(), translate: None, scale: None}
+        Group{items: Vec::new(), transform: Transform::identity()}
     }
 
+    /// Creates a group containing just the item, moved by translate after being scaled
+    /// (about the origin) by scale.
     pub fn item_transformed(item: &'a dyn SvgPositioned, translate: Option<(Coord,Coord)>, scale: Option<Coord>) -> Self {
-        Group{items: vec![item], translate, scale}
+        let (dx, dy) = translate.unwrap_or((0.0, 0.0));
+        let transform = Transform::scale(scale.unwrap_or(1.0)).then(&Transform::translate(dx, dy));
+        Group::item_with_transform(item, transform)
     }
 
-    pub fn add(&mut self, item: &'a dyn SvgPositioned) {
-        self.items.push(item);
+    /// Creates a group containing just the item, with the given transform applied.
+    pub fn item_with_transform(item: &'a dyn SvgPositioned, transform: Transform) -> Self {
+        Group{items: vec![item], transform}
     }
 
-    /// Call this to set the translate for the group.
-    pub fn set_translate(&mut self, translate: Option<(Coord, Coord)>) {
-        self.translate = translate;
+    pub fn add(&mut self, item: &'a dyn SvgPositioned) {
+        self.items.push(item);
     }
 
-    /// Call this to set the scale factor for the group.
-    pub fn set_scale(&mut self, scale: Option<Coord>) {
-        self.scale = scale;
+    /// Call this to set the transform for the group.
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
     }
 
-    /// Returns the translate string.
-    fn get_transform(&self) -> Option<String> {
-        match (self.translate, self.scale) {
-            (None, None) => None,
-            (Some((x,y)), None) => Some(format!("translate({}, {})", x, y)),
-            (None, Some(s)) => Some(format!("scale({})", s)),
-            (Some((x,y)), Some(s)) => Some(format!("translate({}, {}) scale({})", x, y, s)),
-        }
+    pub fn transform(&self) -> Transform {
+        self.transform
     }
 }
 
 impl<'a> Renderable for Group<'a> {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
-        let attributes = match &self.get_transform() {
-            None => Attributes::new(),
-            Some(transform) => Attributes::from([("transform", transform)]),
-        };
-        tag_writer.begin_tag("g", attributes)?;
+        begin_group_tag(tag_writer, &self.transform)?;
         for item in self.items.iter() {
             item.render(tag_writer)?;
         }
@@ -182,20 +595,22 @@ impl<'a> Renderable for Group<'a> {
     }
 }
 
+/// Writes the opening tag of a group, with the transform if there is one.
+fn begin_group_tag(tag_writer: &mut dyn TagWriter, transform: &Transform) -> Result<(), TagWriterError> {
+    let attributes = match &transform.svg_string() {
+        None => Attributes::new(),
+        Some(transform) => Attributes::from([("transform", transform)]),
+    };
+    tag_writer.begin_tag("g", attributes)
+}
+
 
 impl<'a> SvgPositioned for Group<'a> {
-    fn get_bbox(&self) -> Rect {
-        let mut r: Rect = self.items.iter()
+    fn get_bbox(&self) -> BBox {
+        let bbox: BBox = self.items.iter()
             .map(|item| item.get_bbox())
-            .reduce(|accum, rect| accum.cover(&rect))
-            .unwrap_or(Rect::new_cwh((0.0, 0.0), 0.0, 0.0));
-        if let Some(s) = self.scale {
-            r.scale_about_center(s);
-        }
-        if let Some((dx, dy)) = self.translate {
-            r.translate(dx, dy);
-        }
-        r
+            .collect();
+        self.transform.transform_bbox(&bbox)
     }
 }
 
@@ -206,40 +621,444 @@ impl<'a, const N: usize> From<[&'a dyn SvgPositioned; N]> for Group<'a> {
         for item in arr {
             items.push(item);
         }
-        Group{items, translate: None, scale: None}
+        Group{items, transform: Transform::identity()}
     }
 }
 
 
+/// Like a Group, but it owns its items, so it can be built up a piece at a time (in
+/// different functions) and kept around. The items may still borrow things that outlive
+/// the group.
+#[derive(Default)]
+pub struct OwnedGroup<'a> {
+    pub items: Vec<Box<dyn SvgPositioned + 'a>>,
+    transform: Transform,
+}
+
+impl<'a> OwnedGroup<'a> {
+    pub fn new() -> Self {
+        OwnedGroup{items: Vec::new(), transform: Transform::identity()}
+    }
+
+    /// Creates a group containing just the item, with the given transform applied.
+    pub fn item_with_transform(item: impl SvgPositioned + 'a, transform: Transform) -> Self {
+        OwnedGroup{items: vec![Box::new(item)], transform}
+    }
+
+    pub fn add(&mut self, item: impl SvgPositioned + 'a) {
+        self.items.push(Box::new(item));
+    }
+
+    /// Call this to set the transform for the group.
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+}
+
+impl<'a> Renderable for OwnedGroup<'a> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        begin_group_tag(tag_writer, &self.transform)?;
+        for item in self.items.iter() {
+            item.render(tag_writer)?;
+        }
+        tag_writer.end_tag("g")?;
+        Ok(())
+    }
+}
+
+impl<'a> SvgPositioned for OwnedGroup<'a> {
+    fn get_bbox(&self) -> BBox {
+        self.transform.transform_bbox(&self.items.get_bbox())
+    }
+}
+
+
+
+/// Wraps an item so that clicking it follows a link, and/or hovering over it shows a
+/// tooltip (using the SVG <title>, so it works even without any JavaScript).
+pub struct Hyperlink<T> {
+    item: T,
+    href: Option<String>,
+    /// Where the link opens, like "_blank" for a new window.
+    target: Option<String>,
+    tooltip: Option<String>,
+}
+
+impl<T> Hyperlink<T> {
+    /// Wraps the item, which (until a link or tooltip is added) renders just as before.
+    pub fn new(item: T) -> Self {
+        Hyperlink{item, href: None, target: None, tooltip: None}
+    }
+
+    pub fn with_href(mut self, href: &str, target: Option<&str>) -> Self {
+        self.href = Some(href.to_string());
+        self.target = target.map(|x| x.to_string());
+        self
+    }
+
+    pub fn with_tooltip(mut self, tooltip: &str) -> Self {
+        self.tooltip = Some(tooltip.to_string());
+        self
+    }
+}
+
+impl<T: Renderable> Renderable for Hyperlink<T> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        let tag = match &self.href {
+            Some(href) => {
+                let mut attributes = Attributes::from([("href", href)]);
+                if let Some(target) = &self.target {
+                    attributes = attributes.with_field("target", target);
+                }
+                tag_writer.begin_tag("a", attributes)?;
+                "a"
+            },
+            None if self.tooltip.is_some() => {
+                tag_writer.begin_tag("g", Attributes::new())?;
+                "g"
+            },
+            None => return self.item.render(tag_writer),
+        };
+        // --- the <title> must be the first thing in the element it describes ---
+        if let Some(tooltip) = &self.tooltip {
+            tag_writer.tag_with_text("title", Attributes::new(), tooltip)?;
+        }
+        self.item.render(tag_writer)?;
+        tag_writer.end_tag(tag)?;
+        Ok(())
+    }
+}
+
+impl<T: SvgPositioned> SvgPositioned for Hyperlink<T> {
+    fn get_bbox(&self) -> BBox {
+        self.item.get_bbox()
+    }
+}
+
+
+/// How big an Svg says it is.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SvgSize {
+    /// Only a viewBox is given, so the SVG stretches to fill wherever it's put.
+    Unsized,
+    /// Each Coord is drawn as one CSS pixel, and the width and height are given in the
+    /// unit (so a 96 pixel wide drawing in Unit::In says it is "1in" wide).
+    Natural(Unit),
+    /// The drawing is scaled (keeping its shape) to fit on the page inside the margin, and
+    /// centered on it. The width and height are those of the page.
+    FitToPage{page: PageSize, margin: Length},
+    /// The width and height are given as-is; how the drawing fits in them is up to the
+    /// preserveAspectRatio.
+    Explicit{width: Length, height: Length},
+    /// The drawing is not scaled; it is centered on a canvas the size of the page (so
+    /// anything that doesn't fit is cut off).
+    Canvas(PageSize),
+}
+
+/// How the viewBox is fitted into the width and height when they have a different shape.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PreserveAspectRatio {
+    /// The drawing is stretched to fill the width and height.
+    Stretch,
+    /// The drawing is scaled to fit inside, and placed by the alignments (x, y).
+    Meet(Align, Align),
+    /// The drawing is scaled to cover everything, and placed by the alignments (x, y).
+    Slice(Align, Align),
+}
+
+impl PreserveAspectRatio {
+    /// Returns the value of the preserveAspectRatio attribute.
+    pub fn attribute_value(&self) -> String {
+        fn align_str(align: Align) -> &'static str {
+            match align {
+                Align::Start => "Min",
+                Align::Center => "Mid",
+                Align::End => "Max",
+            }
+        }
+        match self {
+            PreserveAspectRatio::Stretch => "none".to_string(),
+            PreserveAspectRatio::Meet(x, y) => format!("x{}Y{} meet", align_str(*x), align_str(*y)),
+            PreserveAspectRatio::Slice(x, y) => format!("x{}Y{} slice", align_str(*x), align_str(*y)),
+        }
+    }
+}
 
 pub struct Svg<'a> {
-    content: Group<'a>,
+    content: Box<dyn SvgPositioned + 'a>,
     margin: Coord,
+    size: SvgSize,
+    background: Option<String>,
+    preserve_aspect_ratio: Option<PreserveAspectRatio>,
+    title: Option<String>,
+    /// (Dublin Core element, value) pairs written inside the <metadata> tag.
+    metadata: Vec<(String, String)>,
 }
 
+/// The namespace of the Dublin Core elements, which is what the <metadata> is written in.
+const DUBLIN_CORE_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+
+/// The names of the Dublin Core elements.
+const DUBLIN_CORE_ELEMENTS: [&str; 15] = [
+    "contributor", "coverage", "creator", "date", "description", "format", "identifier",
+    "language", "publisher", "relation", "rights", "source", "subject", "title", "type",
+];
+
 impl<'a> Svg<'a> {
-    pub fn new(content: Group<'a>, margin: Coord) -> Self {
-        Svg{content, margin}
+    pub fn new(content: impl SvgPositioned + 'a, margin: Coord) -> Self {
+        Svg{
+            content: Box::new(content),
+            margin,
+            size: SvgSize::Unsized,
+            background: None,
+            preserve_aspect_ratio: None,
+            title: None,
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Call this to say how the drawing fits the width and height (None leaves it to the
+    /// SVG default, which is centered and scaled to fit).
+    pub fn set_preserve_aspect_ratio(&mut self, preserve_aspect_ratio: Option<PreserveAspectRatio>) {
+        self.preserve_aspect_ratio = preserve_aspect_ratio;
+    }
+
+    /// Call this to give the SVG a <title>, which viewers may show as its name.
+    pub fn set_title(&mut self, title: Option<&str>) {
+        self.title = title.map(|x| x.to_string());
+    }
+
+    /// Call this to add an entry to the <metadata>, as the Dublin Core element with that
+    /// name (like "source" or "date"). If the name isn't one of the Dublin Core elements,
+    /// this will panic.
+    pub fn add_metadata(&mut self, name: &str, value: &str) {
+        if !DUBLIN_CORE_ELEMENTS.contains(&name) {
+            panic!("The name '{}' is not a Dublin Core element.", name);
+        }
+        self.metadata.push((name.to_string(), value.to_string()));
+    }
+
+    /// Call this to fill the whole viewBox with a color (None leaves it transparent).
+    pub fn set_background(&mut self, background: Option<&str>) {
+        self.background = background.map(|x| x.to_string());
+    }
+
+    /// Call this to set how big the SVG says it is.
+    pub fn set_size(&mut self, size: SvgSize) {
+        self.size = size;
+    }
+
+    /// Returns the viewBox (which has the content in it and margin around it) and, if
+    /// there is one, the (width, height) the SVG should say it is.
+    fn viewbox_and_size(&self) -> (Rect, Option<(Length, Length)>) {
+        // an empty drawing just gets the margin around the origin
+        let bbox = self.content.get_bbox().rect().unwrap_or(Rect::new_ltwh(0.0, 0.0, 0.0, 0.0));
+        let viewbox = bbox.inflated(&Margins::uniform(self.margin));
+        match self.size {
+            SvgSize::Unsized => (viewbox, None),
+            SvgSize::Natural(unit) => {
+                let width = Length::px(viewbox.width()).to(unit);
+                let height = Length::px(viewbox.height()).to(unit);
+                (viewbox, Some((width, height)))
+            },
+            SvgSize::FitToPage{page, margin} => {
+                let printable_width = page.width.to_px() - 2.0 * margin.to_px();
+                let printable_height = page.height.to_px() - 2.0 * margin.to_px();
+                let scale = f64::min(printable_width / viewbox.width(), printable_height / viewbox.height());
+                // --- grow the viewBox to the shape of the page so the content is centered ---
+                let page_viewbox = Rect::new_cwh(
+                    viewbox.center(),
+                    page.width.to_px() / scale,
+                    page.height.to_px() / scale
+                );
+                (page_viewbox, Some((page.width, page.height)))
+            },
+            SvgSize::Explicit{width, height} => (viewbox, Some((width, height))),
+            SvgSize::Canvas(page) => {
+                let canvas_viewbox = Rect::new_cwh(viewbox.center(), page.width.to_px(), page.height.to_px());
+                (canvas_viewbox, Some((page.width, page.height)))
+            },
+        }
     }
 }
 
 impl<'a> Renderable for Svg<'a> {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
-        let bbox = self.content.get_bbox();
+        let (viewbox_rect, size) = self.viewbox_and_size();
         let viewbox: String = format_args!(
             "{} {} {} {}",
-            bbox.left() - self.margin,
-            bbox.top() - self.margin,
-            bbox.width() + 2.0 * self.margin,
-            bbox.height() + 2.0 * self.margin
+            viewbox_rect.left(),
+            viewbox_rect.top(),
+            viewbox_rect.width(),
+            viewbox_rect.height()
         ).to_string();
-        tag_writer.begin_tag("svg", Attributes::from([
+        let mut attributes = Attributes::from([
             ("viewBox", &*viewbox),
             ("xmlns", "http://www.w3.org/2000/svg"),
-        ]))?;
+        ]);
+        if let Some((width, height)) = size {
+            attributes = attributes
+                .with_field("width", width.to_string())
+                .with_field("height", height.to_string());
+        }
+        if let Some(preserve_aspect_ratio) = &self.preserve_aspect_ratio {
+            attributes = attributes.with_field("preserveAspectRatio", preserve_aspect_ratio.attribute_value());
+        }
+        tag_writer.begin_tag("svg", attributes)?;
+        if let Some(title) = &self.title {
+            tag_writer.tag_with_text("title", Attributes::new(), title)?;
+        }
+        if !self.metadata.is_empty() {
+            tag_writer.begin_tag("metadata", Attributes::from([("xmlns:dc", DUBLIN_CORE_NAMESPACE)]))?;
+            for (name, value) in self.metadata.iter() {
+                tag_writer.tag_with_text(&format!("dc:{}", name), Attributes::new(), value)?;
+            }
+            tag_writer.end_tag("metadata")?;
+        }
+        if let Some(background) = &self.background {
+            RoundedRect::new(viewbox_rect, 0.0, Style::filled(background)).render(tag_writer)?;
+        }
+        clear_markers_in_use();
         self.content.render(tag_writer)?;
+        render_marker_defs(tag_writer)?;
         tag_writer.end_tag("svg")?;
         Ok(())
     }
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_writer::TagWriterImpl;
+
+    #[test]
+    fn test_shape_bboxes_include_stroke() {
+        let style = Style::new().with_stroke("#000000", 2.0);
+        let circle = Circle::new((10.0, 10.0), 5.0, style.clone());
+        assert_eq!(circle.get_bbox(), Rect::new_ltrb(4.0, 4.0, 16.0, 16.0).into());
+
+        let line = LineSegment::new((0.0, 0.0), (10.0, 0.0), style.clone());
+        assert_eq!(line.get_bbox(), Rect::new_ltrb(0.0, -1.0, 10.0, 1.0).into());
+
+        let polygon = Polygon::new(vec![(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)], Style::filled("#FF0000"));
+        assert_eq!(polygon.get_bbox(), Rect::new_ltrb(0.0, 0.0, 4.0, 3.0).into());
+        assert!(Polyline::new(vec![], style).get_bbox().is_empty());
+    }
+
+    #[test]
+    fn test_text_alignment_bbox() {
+        // (built directly, since there is no text sizer in tests)
+        let (width, height) = (40.0, 10.0);
+        let mut text = Text{
+            text: "abcd".to_string(),
+            position: (100.0, 50.0),
+            text_size_cached: (width, height),
+            style: Style::new(),
+            anchor: TextAnchor::default(),
+            vertical_align: VerticalAlign::default(),
+        };
+        text.set_alignment(TextAnchor::Start, VerticalAlign::Top);
+        assert_eq!(text.get_bbox(), Rect::new_ltwh(100.0, 50.0, width, height).into());
+        text.set_alignment(TextAnchor::End, VerticalAlign::Bottom);
+        assert_eq!(text.get_bbox(), Rect::new_ltwh(100.0 - width, 50.0 - height, width, height).into());
+        text.set_alignment(TextAnchor::Middle, VerticalAlign::Baseline);
+        let rect = text.get_bbox().rect().unwrap();
+        assert!(rect.top() < 50.0 && rect.bottom() > 50.0);
+    }
+
+    #[test]
+    fn test_css_font_size() {
+        assert_eq!(css_font_size("14px"), Some(14.0));
+        assert_eq!(css_font_size("12pt"), Some(16.0));
+        assert_eq!(css_font_size("1.5em"), Some(1.5 * DEFAULT_FONT_SIZE));
+        assert_eq!(css_font_size("2rem"), Some(2.0 * DEFAULT_FONT_SIZE));
+        assert_eq!(css_font_size("50%"), Some(0.5 * DEFAULT_FONT_SIZE));
+        assert_eq!(css_font_size("larger"), None);
+    }
+
+    #[test]
+    fn test_owned_group_bbox() {
+        let circle = |x| Circle::new((x, 0.0), 1.0, Style::filled("#000000"));
+        let mut group = OwnedGroup::new();
+        group.add(vec![circle(0.0), circle(10.0)]);
+        group.add(None::<Circle>);
+        group.add(Box::new(circle(20.0)) as Box<dyn SvgPositioned>);
+        assert_eq!(group.get_bbox(), Rect::new_ltrb(-1.0, -1.0, 21.0, 1.0).into());
+        group.set_transform(Transform::translate(5.0, 5.0));
+        assert_eq!(group.get_bbox(), Rect::new_ltrb(4.0, 4.0, 26.0, 6.0).into());
+        assert!(OwnedGroup::new().get_bbox().is_empty());
+    }
+
+    #[test]
+    fn test_svg_canvas_and_aspect_ratio() {
+        let mut svg = Svg::new(Circle::new((50.0, 20.0), 10.0, Style::filled("#000000")), 0.0);
+        let page = PageSize{width: Length::px(200.0), height: Length::px(100.0)};
+        svg.set_size(SvgSize::Canvas(page));
+        let (viewbox, size) = svg.viewbox_and_size();
+        assert_eq!(viewbox, Rect::new_ltrb(-50.0, -30.0, 150.0, 70.0));
+        assert_eq!(size, Some((page.width, page.height)));
+        assert_eq!(PreserveAspectRatio::Stretch.attribute_value(), "none");
+        assert_eq!(
+            PreserveAspectRatio::Meet(Align::Start, Align::Center).attribute_value(),
+            "xMinYMid meet"
+        );
+        assert_eq!(
+            PreserveAspectRatio::Slice(Align::End, Align::End).attribute_value(),
+            "xMaxYMax slice"
+        );
+    }
+
+    #[test]
+    fn test_hyperlink() {
+        let render = |item: &dyn Renderable| {
+            let mut output: Vec<u8> = Vec::new();
+            item.render(&mut TagWriterImpl::new(&mut output)).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let dot = || Circle::new((0.0, 0.0), 1.0, Style::filled("#000000"));
+        assert_eq!(render(&Hyperlink::new(dot())), render(&dot()));
+        let linked = render(&Hyperlink::new(dot()).with_href("https://a.b/?x&y", Some("_blank")).with_tooltip("<tip>"));
+        assert!(linked.starts_with("<a href=\"https://a.b/?x&amp;y\" target=\"_blank\">\n  <title>&lt;tip></title>\n  <circle"));
+        assert!(linked.ends_with("</a>\n"));
+        assert!(render(&Hyperlink::new(dot()).with_tooltip("tip")).starts_with("<g>\n  <title>tip</title>"));
+    }
+
+    #[test]
+    fn test_svg_metadata() {
+        let mut svg = Svg::new(Circle::new((0.0, 0.0), 1.0, Style::filled("#000000")), 0.0);
+        svg.set_title(Some("Dots & <Lines>"));
+        svg.add_metadata("source", "dots.xlsx");
+        svg.add_metadata("date", "2024-01-02T03:04:05Z");
+        let mut output: Vec<u8> = Vec::new();
+        svg.render(&mut TagWriterImpl::new(&mut output)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(concat!(
+            "  <title>Dots &amp; &lt;Lines></title>\n",
+            "  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+            "    <dc:source>dots.xlsx</dc:source>\n",
+            "    <dc:date>2024-01-02T03:04:05Z</dc:date>\n",
+            "  </metadata>\n",
+        )), "{}", output);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_svg_metadata_name() {
+        let mut svg = Svg::new(Circle::new((0.0, 0.0), 1.0, Style::filled("#000000")), 0.0);
+        svg.add_metadata("data set", "dots.xlsx");
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+}
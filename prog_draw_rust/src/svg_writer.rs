@@ -39,8 +39,7 @@ impl Attributes {
 
     fn write(&self, output: &mut dyn std::io::Write) -> Result<(), TagWriterError> {
         for (key, val) in &self.fields {
-            // FIXME: For now, this lacks proper escaping for field values
-            write!(*output, " {}=\"{}\"", key, val)?;
+            write!(*output, " {}=\"{}\"", key, xml_escape_attribute_value(val))?;
         }
         Ok(())
     }
@@ -97,6 +96,15 @@ pub fn xml_escape_body_text(s: &str) -> String {
 }
 
 
+/// Given some text that should be the value of an attribute (inside double quotes), this
+/// returns the escaped version of it.
+pub fn xml_escape_attribute_value(s: &str) -> String {
+    let partial_escaped = str::replace(s, "&", "&amp;");
+    let partial_escaped = str::replace(&partial_escaped, "<", "&lt;");
+    str::replace(&partial_escaped, "\"", "&quot;")
+}
+
+
 impl<'a> TagWriterImpl<'a> {
     pub fn new(output: &'a mut dyn std::io::Write) -> Self {
         Self{output, indent_level: 0, indent_str: "  ".to_string()}
@@ -174,3 +182,57 @@ impl<'a> TagWriter for TagWriterImpl<'a> {
 pub trait Renderable {
     fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError>;
 }
+
+impl<T: Renderable + ?Sized> Renderable for &T {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        (**self).render(tag_writer)
+    }
+}
+
+impl<T: Renderable + ?Sized> Renderable for Box<T> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        self.as_ref().render(tag_writer)
+    }
+}
+
+/// Renders nothing if None.
+impl<T: Renderable> Renderable for Option<T> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        match self {
+            None => Ok(()),
+            Some(item) => item.render(tag_writer),
+        }
+    }
+}
+
+/// Renders each item in order (with nothing around them).
+impl<T: Renderable> Renderable for Vec<T> {
+    fn render(&self, tag_writer: &mut dyn TagWriter) -> Result<(), TagWriterError> {
+        for item in self.iter() {
+            item.render(tag_writer)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escaping() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut tag_writer = TagWriterImpl::new(&mut output);
+        tag_writer.tag_with_text(
+            "text",
+            Attributes::from([("title", r#"Say "hi" to <Tom> & 'Jerry'"#)]),
+            "1 < 2 & \"3\""
+        ).unwrap();
+        tag_writer.close().unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<text title=\"Say &quot;hi&quot; to &lt;Tom> &amp; 'Jerry'\">1 &lt; 2 &amp; \"3\"</text>\n"
+        );
+    }
+}
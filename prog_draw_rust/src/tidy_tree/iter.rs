@@ -40,6 +40,8 @@ impl<'a> Iterator for Iter<'a> {
             }
         }
 
+        // --- a node with no parent and no children is the whole tree ---
+        self.finished = true;
         return Some(ans);
     }
 }
@@ -61,7 +63,10 @@ mod iter_test {
 
     #[test]
     fn test_node_iter() {
-        let mut root = Node::new_with_child(0, 1., 1., Node::new(1, 2., 2.));
+        // the children point back at the root, so it mustn't move once they are added
+        let mut root = Node::new(0, 1., 1.);
+        assert_eq!(root.iter().count(), 1);
+        root.append_child(Node::new(1, 2., 2.));
         assert_eq!(root.iter().count(), 2);
         root.append_child(Node::new(2, 3., 3.));
         assert_eq!(root.iter().count(), 3);
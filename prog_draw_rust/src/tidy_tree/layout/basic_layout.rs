@@ -99,7 +99,7 @@ impl BasicLayout {
 #[cfg(test)]
 mod basic_layout_test {
     use super::{BasicLayout, BoundingBox};
-    use crate::{layout::Layout, Node};
+    use crate::tidy_tree::{layout::Layout, Node};
 
     #[test]
     fn easy_test_0() {
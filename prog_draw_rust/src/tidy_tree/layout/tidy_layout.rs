@@ -517,7 +517,7 @@ fn invalidate_extreme_thread(node: &mut Node) {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::node::Node;
+    use crate::tidy_tree::node::Node;
     #[test]
     fn test_tidy_layout() {
         let mut tidy = TidyLayout::new(1., 1.);
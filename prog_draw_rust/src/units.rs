@@ -0,0 +1,166 @@
+//
+// Physical units for when a drawing needs to come out at a real size (like when it gets
+// printed). Everything in geometry is measured in CSS pixels, and there are 96 of those
+// to the inch; these types convert between that and points, millimetres, and inches.
+//
+
+use std::fmt::{Display, Formatter};
+use crate::geometry::Coord;
+
+
+/// The units a Length can be measured in. These are the same as the CSS units of the
+/// same names.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Unit {
+    Px,
+    Pt,
+    Mm,
+    In,
+}
+
+impl Unit {
+    /// Returns how many of this unit there are in an inch.
+    pub fn per_inch(&self) -> Coord {
+        match self {
+            Unit::Px => 96.0,
+            Unit::Pt => 72.0,
+            Unit::Mm => 25.4,
+            Unit::In => 1.0,
+        }
+    }
+
+    /// Returns the suffix used for this unit in SVG and CSS.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Unit::Px => "px",
+            Unit::Pt => "pt",
+            Unit::Mm => "mm",
+            Unit::In => "in",
+        }
+    }
+}
+
+
+/// A distance measured in some particular Unit.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Length {
+    value: Coord,
+    unit: Unit,
+}
+
+impl Length {
+    pub fn new(value: Coord, unit: Unit) -> Self {
+        Length{value, unit}
+    }
+
+    pub fn px(value: Coord) -> Self {Length::new(value, Unit::Px)}
+    pub fn pt(value: Coord) -> Self {Length::new(value, Unit::Pt)}
+    pub fn mm(value: Coord) -> Self {Length::new(value, Unit::Mm)}
+    pub fn inches(value: Coord) -> Self {Length::new(value, Unit::In)}
+
+    /// Reads a length written the way CSS does, like "14pt". A bare number is in px. Returns
+    /// None if it isn't a number followed by one of the Units.
+    pub fn parse(css: &str) -> Option<Length> {
+        let css = css.trim();
+        for unit in [Unit::Px, Unit::Pt, Unit::Mm, Unit::In] {
+            if let Some(number) = css.strip_suffix(unit.suffix()) {
+                return number.trim().parse().ok().map(|value| Length::new(value, unit));
+            }
+        }
+        css.parse().ok().map(Length::px)
+    }
+
+    pub fn value(&self) -> Coord {self.value}
+    pub fn unit(&self) -> Unit {self.unit}
+
+    /// Returns the same distance measured in a different unit.
+    pub fn to(&self, unit: Unit) -> Length {
+        Length::new(self.value * unit.per_inch() / self.unit.per_inch(), unit)
+    }
+
+    /// Returns this distance in CSS pixels, which is what geometry Coords are measured in.
+    pub fn to_px(&self) -> Coord {
+        self.to(Unit::Px).value
+    }
+
+    /// Returns this distance multiplied by s.
+    pub fn scaled(&self, s: Coord) -> Length {
+        Length::new(self.value * s, self.unit)
+    }
+}
+
+impl Display for Length {
+    /// Writes it the way SVG and CSS expect, like "297mm".
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.suffix())
+    }
+}
+
+
+/// The size of a sheet of paper.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PageSize {
+    pub width: Length,
+    pub height: Length,
+}
+
+impl PageSize {
+    pub const A3: PageSize = PageSize{width: Length{value: 297.0, unit: Unit::Mm}, height: Length{value: 420.0, unit: Unit::Mm}};
+    pub const A4: PageSize = PageSize{width: Length{value: 210.0, unit: Unit::Mm}, height: Length{value: 297.0, unit: Unit::Mm}};
+    pub const TABLOID: PageSize = PageSize{width: Length{value: 11.0, unit: Unit::In}, height: Length{value: 17.0, unit: Unit::In}};
+    pub const LETTER: PageSize = PageSize{width: Length{value: 8.5, unit: Unit::In}, height: Length{value: 11.0, unit: Unit::In}};
+
+    /// Returns the page turned so it is wider than it is tall.
+    pub fn landscape(&self) -> PageSize {
+        if self.width.to_px() >= self.height.to_px() {
+            *self
+        } else {
+            PageSize{width: self.height, height: self.width}
+        }
+    }
+
+    /// Returns the page turned so it is taller than it is wide.
+    pub fn portrait(&self) -> PageSize {
+        if self.width.to_px() <= self.height.to_px() {
+            *self
+        } else {
+            PageSize{width: self.height, height: self.width}
+        }
+    }
+
+    /// Returns one of the known page sizes by its name ("A3", "A4", "TABLOID", or
+    /// "LETTER"), in portrait, or None if the name isn't known.
+    pub fn by_name(name: &str) -> Option<PageSize> {
+        match name {
+            "A3" => Some(PageSize::A3),
+            "A4" => Some(PageSize::A4),
+            "TABLOID" => Some(PageSize::TABLOID),
+            "LETTER" => Some(PageSize::LETTER),
+            _ => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Length::inches(1.0).to(Unit::Pt), Length::pt(72.0));
+        assert_eq!(Length::inches(2.0).to_px(), 192.0);
+        assert!((Length::mm(25.4).to(Unit::In).value() - 1.0).abs() < 1e-12);
+        assert_eq!(Length::mm(297.0).to_string(), "297mm");
+        assert_eq!(PageSize::TABLOID.landscape().width, Length::inches(17.0));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Length::parse("14pt"), Some(Length::pt(14.0)));
+        assert_eq!(Length::parse(" 2.5 mm "), Some(Length::mm(2.5)));
+        assert_eq!(Length::parse("12"), Some(Length::px(12.0)));
+        assert_eq!(Length::parse("1.2em"), None);
+        assert_eq!(Length::parse("large"), None);
+    }
+}